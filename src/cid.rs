@@ -0,0 +1,179 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use digest::{Digest, DynDigest, InvalidBufferSize};
+use sha2::Sha256;
+
+use crate::encoding::Encoding;
+use crate::multihash::{self, push_varint};
+
+/// The size of the chunks a file is split into, as with the default chunker of `ipfs add`.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// The maximum number of links of a node in the balanced layout of `ipfs add`.
+const MAX_LINKS: usize = 174;
+
+/// Multicodec codes of raw blocks and dag-pb nodes.
+const RAW: u8 = 0x55;
+const DAG_PB: u8 = 0x70;
+
+/// The size of a CIDv1 with a SHA-256 multihash in bytes.
+const CID_SIZE: usize = 36;
+
+/// A link to a block of the UnixFS DAG of a file.
+#[derive(Clone)]
+struct Link {
+    cid: Vec<u8>,
+    /// The size of the block and all blocks it links to.
+    tsize: u64,
+    /// The size of the part of the file in the block and all blocks it links to.
+    filesize: u64,
+}
+
+/// Calculates the CIDv1 of a file as `ipfs add --cid-version=1 --raw-leaves` does, i.e., the CID
+/// of a single raw block for files of at most one chunk, and otherwise the CID of the root of a
+/// balanced UnixFS DAG of dag-pb nodes whose leaves are raw blocks of one chunk each.
+#[derive(Clone, Default)]
+pub(crate) struct Cid {
+    chunk: Vec<u8>,
+    /// Links to the completed blocks that have no parent yet, by their height in the DAG.
+    levels: Vec<Vec<Link>>,
+}
+
+/// Returns the CIDv1 of a block with `codec` and `content`.
+fn cid(codec: u8, content: &[u8]) -> Vec<u8> {
+    let mut cid = vec![0x01, codec, 0x12, 0x20];
+    cid.extend_from_slice(&Sha256::digest(content));
+    cid
+}
+
+/// Appends a length-delimited protobuf field with `tag` and `value` to `bytes`.
+fn push_bytes(bytes: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    bytes.push(tag);
+    push_varint(bytes, value.len() as u64);
+    bytes.extend_from_slice(value);
+}
+
+/// Returns a link to a dag-pb node that links to `links` and describes them as a UnixFS file.
+fn node(links: Vec<Link>) -> Link {
+    let filesize = links.iter().map(|link| link.filesize).sum();
+
+    // UnixFS Data with Type = File, filesize and the blocksizes of the children
+    let mut data = vec![0x08, 0x02, 0x18];
+    push_varint(&mut data, filesize);
+    for link in &links {
+        data.push(0x20);
+        push_varint(&mut data, link.filesize);
+    }
+
+    // PBNode with the Links before the Data, each PBLink with Hash, an empty Name and Tsize
+    let mut node = Vec::new();
+    for link in &links {
+        let mut pb_link = Vec::new();
+        push_bytes(&mut pb_link, 0x0a, &link.cid);
+        push_bytes(&mut pb_link, 0x12, b"");
+        pb_link.push(0x18);
+        push_varint(&mut pb_link, link.tsize);
+        push_bytes(&mut node, 0x12, &pb_link);
+    }
+    push_bytes(&mut node, 0x0a, &data);
+
+    let tsize = node.len() as u64 + links.iter().map(|link| link.tsize).sum::<u64>();
+    Link { cid: cid(DAG_PB, &node), tsize, filesize }
+}
+
+impl Cid {
+    /// Adds a link to a block at `height` in the DAG, first linking the full set of blocks at that
+    /// height from a new parent node.
+    fn push(&mut self, height: usize, link: Link) {
+        if self.levels.len() <= height {
+            self.levels.push(Vec::new());
+        }
+        if self.levels[height].len() == MAX_LINKS {
+            let parent = node(std::mem::take(&mut self.levels[height]));
+            self.push(height + 1, parent);
+        }
+        self.levels[height].push(link);
+    }
+
+    /// Adds the current chunk as a raw leaf block.
+    fn push_chunk(&mut self) {
+        let size = self.chunk.len() as u64;
+        let leaf = Link { cid: cid(RAW, &self.chunk), tsize: size, filesize: size };
+        self.chunk.clear();
+        self.push(0, leaf);
+    }
+
+    /// Returns the CID of the root of the DAG.
+    fn root(&mut self) -> Vec<u8> {
+        if !self.chunk.is_empty() || self.levels.is_empty() {
+            self.push_chunk();
+        }
+        let mut height = 0;
+        loop {
+            let top = self.levels[height + 1..].iter().all(Vec::is_empty);
+            if top && self.levels[height].len() == 1 {
+                return self.levels[height].remove(0).cid;
+            }
+            if !self.levels[height].is_empty() {
+                let parent = node(std::mem::take(&mut self.levels[height]));
+                self.push(height + 1, parent);
+            }
+            height += 1;
+        }
+    }
+}
+
+impl DynDigest for Cid {
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let n = (CHUNK_SIZE - self.chunk.len()).min(data.len());
+            self.chunk.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if self.chunk.len() == CHUNK_SIZE {
+                self.push_chunk();
+            }
+        }
+    }
+
+    fn finalize_into(mut self, buf: &mut [u8]) -> std::result::Result<(), InvalidBufferSize> {
+        self.finalize_into_reset(buf)
+    }
+
+    fn finalize_into_reset(&mut self, buf: &mut [u8]) -> std::result::Result<(), InvalidBufferSize> {
+        if buf.len() != CID_SIZE {
+            return Err(InvalidBufferSize);
+        }
+        buf.copy_from_slice(&self.root());
+        self.reset();
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        *self = Cid::default();
+    }
+
+    fn output_size(&self) -> usize {
+        CID_SIZE
+    }
+
+    fn box_clone(&self) -> Box<dyn DynDigest> {
+        Box::new(self.clone())
+    }
+}
+
+/// Encodes `cid` with `encoding`, prefixed with the multibase prefix of the encoding.
+pub(crate) fn encode(cid: &[u8], encoding: Encoding) -> Option<String> {
+    Some(format!("{}{}", encoding.multibase()?, encoding.encode(cid)))
+}
+
+/// Decodes a multibase encoded CIDv1 of a raw block or a dag-pb node with a SHA-256 multihash, as
+/// calculated by [`Cid`], returning the encoding and the CID.
+pub(crate) fn decode(s: &str) -> Option<(Encoding, Vec<u8>)> {
+    let (encoding, cid) = multihash::multibase_decode(s)?;
+    match cid.as_slice() {
+        [0x01, RAW | DAG_PB, 0x12, 0x20, ..] if cid.len() == CID_SIZE => Some((encoding, cid)),
+        _ => None,
+    }
+}
@@ -0,0 +1,167 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use std::fmt;
+use std::str::FromStr;
+
+/// The alphabet of Nix's base32 encoding, which omits the letters e, o, u and t.
+const NIX32_ALPHABET: &[u8; 32] = b"0123456789abcdfghijklmnpqrsvwxyz";
+
+/// How digests are encoded in destination names.
+///
+/// All encodings produce names that consist of ASCII letters, digits, `-` and `_`. Hexadecimal,
+/// base32 and Nix's base32 names are lowercase and decoded regardless of case, so they are
+/// distinct on case-insensitive filesystems. Base64url and base58 names are mixed case, so on a
+/// case-insensitive filesystem two names that differ only in case refer to the same file. Such a
+/// name is treated like any other existing destination with different content and never replaced
+/// silently.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Lowercase hexadecimal, e.g. 64 characters for SHA-256.
+    #[default]
+    Hex,
+    /// Lowercase RFC 4648 base32 without padding, e.g. 52 characters for SHA-256.
+    Base32,
+    /// RFC 4648 base64 with the URL and filename safe alphabet and without padding, e.g. 43
+    /// characters for SHA-256.
+    Base64Url,
+    /// Base58 with the Bitcoin alphabet, e.g. 43 or 44 characters for SHA-256.
+    Base58,
+    /// Nix's base32, e.g. 52 characters for SHA-256.
+    Nix32,
+}
+
+impl Encoding {
+    /// All encodings, in the order they are tried when decoding names of unknown encoding.
+    pub const ALL: &'static [Encoding] = &[
+        Encoding::Hex, Encoding::Base32, Encoding::Base64Url, Encoding::Base58, Encoding::Nix32,
+    ];
+
+    /// Returns the name of the encoding as accepted by [`Encoding::from_str`].
+    pub fn name(&self) -> &'static str {
+        match self {
+                  Encoding::Hex => "hex",
+               Encoding::Base32 => "base32",
+            Encoding::Base64Url => "base64url",
+               Encoding::Base58 => "base58",
+                Encoding::Nix32 => "nix32",
+        }
+    }
+
+    /// Returns the multibase prefix of the encoding, or `None` if it has none.
+    pub fn multibase(&self) -> Option<char> {
+        match self {
+                  Encoding::Hex => Some('f'),
+               Encoding::Base32 => Some('b'),
+            Encoding::Base64Url => Some('u'),
+               Encoding::Base58 => Some('z'),
+                Encoding::Nix32 => None,
+        }
+    }
+
+    /// Returns whether encoded digests may differ only in the case of their letters.
+    pub fn is_mixed_case(&self) -> bool {
+        matches!(self, Encoding::Base64Url | Encoding::Base58)
+    }
+
+    /// Returns whether `s` consists only of characters that digests encoded with the encoding
+    /// contain, regardless of case where [`Encoding::decode`] ignores it. Unlike
+    /// [`Encoding::decode`], this also holds for digests shortened to any number of characters.
+    pub(crate) fn is_alphabet(&self, s: &str) -> bool {
+        s.chars().all(|c| match self {
+                  Encoding::Hex => c.is_ascii_hexdigit(),
+               Encoding::Base32 => matches!(c.to_ascii_lowercase(), 'a'..='z' | '2'..='7'),
+            Encoding::Base64Url => c.is_ascii_alphanumeric() || c == '-' || c == '_',
+               Encoding::Base58 => c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l'),
+                Encoding::Nix32 => c.is_ascii()
+                                       && NIX32_ALPHABET.contains(&(c.to_ascii_lowercase() as u8)),
+        })
+    }
+
+    /// Encodes `digest` as a string.
+    pub fn encode(&self, digest: &[u8]) -> String {
+        match self {
+                  Encoding::Hex => hex::encode(digest),
+               Encoding::Base32 => data_encoding::BASE32_NOPAD.encode(digest).to_ascii_lowercase(),
+            Encoding::Base64Url => data_encoding::BASE64URL_NOPAD.encode(digest),
+               Encoding::Base58 => bs58::encode(digest).into_string(),
+                Encoding::Nix32 => nix32_encode(digest),
+        }
+    }
+
+    /// Decodes a digest encoded with [`Encoding::encode`], returning `None` if `s` is not a valid
+    /// encoding of any digest.
+    pub fn decode(&self, s: &str) -> Option<Vec<u8>> {
+        match self {
+                  Encoding::Hex => hex::decode(s).ok(),
+               Encoding::Base32 => data_encoding::BASE32_NOPAD
+                                        .decode(s.to_ascii_uppercase().as_bytes()).ok(),
+            Encoding::Base64Url => data_encoding::BASE64URL_NOPAD.decode(s.as_bytes()).ok(),
+               Encoding::Base58 => bs58::decode(s).into_vec().ok(),
+                Encoding::Nix32 => nix32_decode(&s.to_ascii_lowercase()),
+        }
+    }
+}
+
+/// Error returned when parsing an [`Encoding`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseEncodingError(String);
+
+impl fmt::Display for ParseEncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid encoding {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseEncodingError {}
+
+impl FromStr for Encoding {
+    type Err = ParseEncodingError;
+
+    fn from_str(s: &str) -> Result<Encoding, ParseEncodingError> {
+        Encoding::ALL.iter().copied()
+            .find(|encoding| encoding.name() == s)
+            .ok_or_else(|| ParseEncodingError(s.to_string()))
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Encodes `bytes` in Nix's base32, which takes 5-bit groups starting from the least significant
+/// bits of the last byte, unlike RFC 4648 base32.
+fn nix32_encode(bytes: &[u8]) -> String {
+    let len = (bytes.len() * 8).div_ceil(5);
+    (0..len).rev().map(|n| {
+        let (i, j) = (n * 5 / 8, n * 5 % 8);
+        let low = bytes[i] >> j;
+        let high = bytes.get(i + 1).map_or(0, |&b| b.checked_shl(8 - j as u32).unwrap_or(0));
+        NIX32_ALPHABET[((low | high) & 0x1f) as usize] as char
+    }).collect()
+}
+
+/// Decodes a string encoded with [`nix32_encode`].
+fn nix32_decode(s: &str) -> Option<Vec<u8>> {
+    let len = s.len() * 5 / 8;
+    if (len * 8).div_ceil(5) != s.len() {
+        return None;
+    }
+
+    let mut bytes = vec![0u8; len];
+    for (n, c) in s.bytes().rev().enumerate() {
+        let digit = NIX32_ALPHABET.iter().position(|&a| a == c)? as u16;
+        let (i, j) = (n * 5 / 8, n * 5 % 8);
+        let value = digit << j;
+        bytes[i] |= value as u8;
+        match bytes.get_mut(i + 1) {
+            Some(next) => *next |= (value >> 8) as u8,
+            None if value >> 8 != 0 => return None,
+            None => (),
+        }
+    }
+    Some(bytes)
+}
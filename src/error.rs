@@ -0,0 +1,110 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::Status;
+use crate::encoding::Encoding;
+
+/// Errors returned by the `shamv` library.
+#[derive(Debug)]
+pub enum Error {
+    /// The named digest algorithm is not supported.
+    UnsupportedAlg(String),
+    /// Multihash names were requested with an encoding that has no multibase prefix.
+    NoMultibase(Encoding),
+    /// Multihash names were requested with a digest algorithm that has no multihash code.
+    NoMultihashCode(String),
+    /// The file does not exist.
+    FileNotFound(PathBuf),
+    /// The target directory does not exist or could not be created.
+    TargetDirectory { path: PathBuf, source: io::Error },
+    /// The directory could not be walked.
+    Walk { path: PathBuf, source: io::Error },
+    /// The digest of the file could not be calculated.
+    Digest { path: PathBuf, source: io::Error },
+    /// The file could not be renamed to its destination path.
+    Rename { path: PathBuf, source: io::Error },
+    /// The file could not be copied to its destination path.
+    Copy { path: PathBuf, source: io::Error },
+    /// The file could not be linked to from its destination path.
+    Link { path: PathBuf, source: io::Error },
+    /// The file could not be hard linked to from its destination path on another filesystem.
+    CrossDevice { path: PathBuf, destination: PathBuf },
+    /// The destination path of the file exists with different content.
+    DestinationExists { path: PathBuf, destination: PathBuf },
+}
+
+impl Error {
+    /// Returns the exit status of the `shamv` utility for this error.
+    pub fn status(&self) -> Status {
+        match self {
+            Error::UnsupportedAlg(_) => Status::UnsupportedAlg,
+            Error::NoMultibase(_)
+            | Error::NoMultihashCode(_) => Status::UsageError,
+            Error::FileNotFound(_)
+            | Error::TargetDirectory { .. }
+            | Error::Walk { .. } => Status::FileNotFound,
+            Error::Digest { .. } => Status::DigestError,
+            Error::Rename { .. }
+            | Error::Copy { .. }
+            | Error::Link { .. }
+            | Error::CrossDevice { .. } => Status::RenameError,
+            Error::DestinationExists { .. } => Status::DestinationExists,
+        }
+    }
+}
+
+/// Result type of the `shamv` library.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Returns the final component of `path` for use in messages, or the whole path if it has none.
+fn file_name(path: &Path) -> &std::ffi::OsStr {
+    path.file_name().unwrap_or(path.as_os_str())
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnsupportedAlg(alg) => write!(f, "unsupported algorithm {}", alg),
+            Error::NoMultibase(encoding) => write!(f, "encoding {} has no multibase prefix",
+                encoding),
+            Error::NoMultihashCode(alg) => write!(f, "algorithm {} has no multihash code", alg),
+            Error::FileNotFound(path) => write!(f, "file not found {}", path.display()),
+            Error::TargetDirectory { path, source } => write!(f, "target directory {}: {}",
+                path.display(), source),
+            Error::Walk { path, source } => write!(f, "error walking directory {}: {}",
+                path.display(), source),
+            Error::Digest { path, source } => write!(f, "error calculating digest for: {:?}: {}",
+                file_name(path), source),
+            Error::Rename { path, source } => write!(f, "error renaming file {:?}: {}",
+                file_name(path), source),
+            Error::Copy { path, source } => write!(f, "error copying file {:?}: {}",
+                file_name(path), source),
+            Error::Link { path, source } => write!(f, "error linking to file {:?}: {}",
+                file_name(path), source),
+            Error::CrossDevice { path, destination } => write!(f,
+                "cannot hard link {:?} to {:?} on another filesystem, copy or symbolically link it instead",
+                destination, file_name(path)),
+            Error::DestinationExists { path, destination } => write!(f,
+                "refusing to overwrite {:?} with {:?}: content differs", destination, file_name(path)),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::TargetDirectory { source, .. }
+            | Error::Walk { source, .. }
+            | Error::Digest { source, .. }
+            | Error::Rename { source, .. }
+            | Error::Copy { source, .. }
+            | Error::Link { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
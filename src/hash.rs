@@ -0,0 +1,438 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use digest::{DynDigest, ExtendableOutputReset, InvalidBufferSize, Reset, Update, VariableOutput};
+
+use crate::cid::Cid;
+use crate::error::{Error, Result};
+
+/// Returns an instance of the digest algorithm named `s`, or `None` if the algorithm is not
+/// supported. The empty string selects the default algorithm, SHA-256.
+///
+/// The legacy algorithms MD5 and SHA-1 are supported only with the `legacy-hashes` feature. They
+/// are not collision resistant, see [`is_weak_alg`].
+///
+/// The extendable-output functions SHAKE128 and SHAKE256 produce 256 and 512 bits of output by
+/// default, and BLAKE2b and BLAKE2s 512 and 256 bits respectively. Another output length in bits
+/// can be selected by appending it to the name, e.g., `shake128-160` or `blake2b-160`.
+///
+/// The `cid` algorithm produces the CIDv1 that `ipfs add --cid-version=1 --raw-leaves` gives the
+/// content, which names the content on IPFS.
+pub fn get_digest_alg(s: &str) -> Option<Box<dyn DynDigest>> {
+    match s {
+                "" => Some(Box::new(sha2::Sha256::default())),  // Default digest algorithm
+          "sha224" => Some(Box::new(sha2::Sha224::default())),
+          "sha256" => Some(Box::new(sha2::Sha256::default())),
+          "sha384" => Some(Box::new(sha2::Sha384::default())),
+          "sha512" => Some(Box::new(sha2::Sha512::default())),
+      "sha512-224" => Some(Box::new(sha2::Sha512_224::default())),
+      "sha512-256" => Some(Box::new(sha2::Sha512_256::default())),
+        "sha3-224" => Some(Box::new(sha3::Sha3_224::default())),
+        "sha3-256" => Some(Box::new(sha3::Sha3_256::default())),
+        "sha3-384" => Some(Box::new(sha3::Sha3_384::default())),
+        "sha3-512" => Some(Box::new(sha3::Sha3_512::default())),
+        "shake128" => Some(Box::new(Xof::<sha3::Shake128>::new(32))),
+        "shake256" => Some(Box::new(Xof::<sha3::Shake256>::new(64))),
+         "blake2b" => Some(Box::new(blake2::Blake2b512::default())),
+         "blake2s" => Some(Box::new(blake2::Blake2s256::default())),
+          "blake3" => Some(Box::new(Blake3::default())),
+             "cid" => Some(Box::new(Cid::default())),
+        #[cfg(feature = "legacy-hashes")]
+             "md5" => Some(Box::new(md5::Md5::default())),
+        #[cfg(feature = "legacy-hashes")]
+            "sha1" => Some(Box::new(sha1::Sha1::default())),
+                 _ => get_variable_alg(s),
+    }
+}
+
+/// Returns an instance of the variable output length algorithm named `s`, e.g., `shake128-160`.
+fn get_variable_alg(s: &str) -> Option<Box<dyn DynDigest>> {
+    let (name, bits) = s.rsplit_once('-')?;
+    let bits: usize = bits.parse().ok()?;
+    if bits == 0 || !bits.is_multiple_of(8) {
+        return None;
+    }
+
+    match name {
+        "shake128" => Some(Box::new(Xof::<sha3::Shake128>::new(bits / 8))),
+        "shake256" => Some(Box::new(Xof::<sha3::Shake256>::new(bits / 8))),
+         "blake2b" => Some(Box::new(Var::<blake2::Blake2bVar>::new(bits / 8)?)),
+         "blake2s" => Some(Box::new(Var::<blake2::Blake2sVar>::new(bits / 8)?)),
+                 _ => None,
+    }
+}
+
+/// An extendable-output function that produces a fixed amount of output.
+#[derive(Clone)]
+struct Xof<T> {
+    hasher: T,
+    output_size: usize,
+}
+
+impl<T: Default> Xof<T> {
+    fn new(output_size: usize) -> Xof<T> {
+        Xof { hasher: T::default(), output_size }
+    }
+}
+
+impl<T> DynDigest for Xof<T>
+where
+    T: Update + ExtendableOutputReset + Reset + Clone + 'static,
+{
+    fn update(&mut self, data: &[u8]) {
+        Update::update(&mut self.hasher, data);
+    }
+
+    fn finalize_into(mut self, buf: &mut [u8]) -> std::result::Result<(), InvalidBufferSize> {
+        self.finalize_into_reset(buf)
+    }
+
+    fn finalize_into_reset(&mut self, buf: &mut [u8]) -> std::result::Result<(), InvalidBufferSize> {
+        if buf.len() != self.output_size {
+            return Err(InvalidBufferSize);
+        }
+        self.hasher.finalize_xof_reset_into(buf);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        Reset::reset(&mut self.hasher);
+    }
+
+    fn output_size(&self) -> usize {
+        self.output_size
+    }
+
+    fn box_clone(&self) -> Box<dyn DynDigest> {
+        Box::new(self.clone())
+    }
+}
+
+/// A hash function with an output size selected at runtime.
+#[derive(Clone)]
+struct Var<T> {
+    hasher: T,
+    initial: T,
+}
+
+impl<T: VariableOutput + Clone> Var<T> {
+    /// Returns `None` if the hash function does not support `output_size`.
+    fn new(output_size: usize) -> Option<Var<T>> {
+        let initial = T::new(output_size).ok()?;
+        Some(Var { hasher: initial.clone(), initial })
+    }
+}
+
+impl<T> DynDigest for Var<T>
+where
+    T: Update + VariableOutput + Clone + 'static,
+{
+    fn update(&mut self, data: &[u8]) {
+        Update::update(&mut self.hasher, data);
+    }
+
+    fn finalize_into(self, buf: &mut [u8]) -> std::result::Result<(), InvalidBufferSize> {
+        self.hasher.finalize_variable(buf)
+    }
+
+    fn finalize_into_reset(&mut self, buf: &mut [u8]) -> std::result::Result<(), InvalidBufferSize> {
+        std::mem::replace(&mut self.hasher, self.initial.clone()).finalize_variable(buf)
+    }
+
+    fn reset(&mut self) {
+        self.hasher = self.initial.clone();
+    }
+
+    fn output_size(&self) -> usize {
+        self.hasher.output_size()
+    }
+
+    fn box_clone(&self) -> Box<dyn DynDigest> {
+        Box::new(self.clone())
+    }
+}
+
+/// The BLAKE3 hash function with its default output size of 256 bits.
+#[derive(Clone, Default)]
+struct Blake3 {
+    hasher: blake3::Hasher,
+}
+
+impl DynDigest for Blake3 {
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    fn finalize_into(mut self, buf: &mut [u8]) -> std::result::Result<(), InvalidBufferSize> {
+        self.finalize_into_reset(buf)
+    }
+
+    fn finalize_into_reset(&mut self, buf: &mut [u8]) -> std::result::Result<(), InvalidBufferSize> {
+        if buf.len() != blake3::OUT_LEN {
+            return Err(InvalidBufferSize);
+        }
+        buf.copy_from_slice(self.hasher.finalize().as_bytes());
+        self.hasher.reset();
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.hasher.reset();
+    }
+
+    fn output_size(&self) -> usize {
+        blake3::OUT_LEN
+    }
+
+    fn box_clone(&self) -> Box<dyn DynDigest> {
+        Box::new(self.clone())
+    }
+}
+
+/// Names of the supported digest algorithms, as understood by [`Algorithm::new`].
+pub const ALGORITHMS: &[&str] = &[
+    "sha224", "sha256", "sha384", "sha512", "sha512-224", "sha512-256",
+    "sha3-224", "sha3-256", "sha3-384", "sha3-512",
+    "shake128", "shake256",
+    "blake2b-512", "blake2b-256", "blake2s-256",
+    "blake3",
+    "git-sha1", "git-sha256",
+    #[cfg(feature = "legacy-hashes")] "md5",
+    #[cfg(feature = "legacy-hashes")] "sha1",
+];
+
+/// Returns whether the digest algorithm named `s` is known to be vulnerable to collisions, so
+/// that files with different content may be given the same name on purpose.
+pub fn is_weak_alg(s: &str) -> bool {
+    matches!(s, "md5" | "sha1" | "git-sha1")
+}
+
+/// Returns whether the digest algorithm named `s` is only available with the `legacy-hashes`
+/// feature, so that it is used for names created by older tools rather than for new ones.
+pub fn is_legacy_alg(s: &str) -> bool {
+    matches!(s, "md5" | "sha1")
+}
+
+/// A digest algorithm together with the name it was selected by.
+pub struct Algorithm {
+    name: String,
+    digest: Box<dyn DynDigest>,
+    parallel: Option<Parallel>,
+    /// Whether the input is hashed as a git blob object, i.e., prefixed with its length.
+    blob: bool,
+}
+
+/// A BLAKE3 hasher that hashes large inputs on several threads using the BLAKE3 tree mode.
+struct Parallel {
+    hasher: blake3::Hasher,
+    pool: rayon_core::ThreadPool,
+}
+
+impl Algorithm {
+    /// Selects the digest algorithm named `name`, as understood by [`get_digest_alg`].
+    ///
+    /// In addition, `git-sha1` and `git-sha256` hash the input as a git blob object, i.e.,
+    /// `blob <length>\0` followed by the input, so the digest is the object ID that
+    /// `git hash-object` gives the input in a repository with the SHA-1 or SHA-256 object format.
+    pub fn new(name: &str) -> Result<Algorithm> {
+        let (digest, blob): (Option<Box<dyn DynDigest>>, bool) = match name {
+              "git-sha1" => (Some(Box::new(sha1::Sha1::default())), true),
+            "git-sha256" => (Some(Box::new(sha2::Sha256::default())), true),
+                       _ => (get_digest_alg(name), false),
+        };
+        match digest {
+            Some(digest) => Ok(Algorithm {
+                name: if name.is_empty() { "sha256" } else { name }.to_string(),
+                digest,
+                parallel: None,
+                blob,
+            }),
+            None => Err(Error::UnsupportedAlg(name.to_string())),
+        }
+    }
+
+    /// Hashes each input on up to `threads` threads if the algorithm supports it, which is
+    /// currently the case for BLAKE3 only. The digest does not depend on the number of threads.
+    pub fn with_threads(mut self, threads: usize) -> Algorithm {
+        self.parallel = None;
+        if self.name == "blake3" && threads > 1 {
+            // Without a thread pool the input is simply hashed on the calling thread
+            if let Ok(pool) = rayon_core::ThreadPoolBuilder::new().num_threads(threads).build() {
+                self.parallel = Some(Parallel { hasher: blake3::Hasher::new(), pool });
+            }
+        }
+        self
+    }
+
+    /// Returns the name of the algorithm.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the size of the digest in bytes.
+    pub fn output_size(&self) -> usize {
+        self.digest.output_size()
+    }
+
+    /// Returns whether the algorithm is known to be vulnerable to collisions.
+    pub fn is_weak(&self) -> bool {
+        is_weak_alg(&self.name)
+    }
+
+    /// Returns whether the algorithm is only available with the `legacy-hashes` feature.
+    pub fn is_legacy(&self) -> bool {
+        is_legacy_alg(&self.name)
+    }
+
+    fn reset(&mut self) {
+        match &mut self.parallel {
+            Some(parallel) => { parallel.hasher.reset(); },
+            None => self.digest.reset(),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match &mut self.parallel {
+            Some(Parallel { hasher, pool }) => pool.install(|| { hasher.update_rayon(data); }),
+            None => self.digest.update(data),
+        }
+    }
+
+    fn finalize_reset(&mut self) -> Vec<u8> {
+        match &mut self.parallel {
+            Some(parallel) => {
+                let digest = parallel.hasher.finalize().as_bytes().to_vec();
+                parallel.hasher.reset();
+                digest
+            },
+            None => self.digest.finalize_reset().into_vec(),
+        }
+    }
+}
+
+impl Default for Algorithm {
+    fn default() -> Algorithm {
+        Algorithm::new("").expect("default algorithm is supported")
+    }
+}
+
+/// Size of the chunks in which input is read and fed to the digest algorithm.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Size of the chunks in which input is read when it is hashed on several threads, large enough
+/// to split between the threads.
+pub const PARALLEL_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Calculates the digest of everything read from `reader`.
+///
+/// The input is read in chunks of [`CHUNK_SIZE`] bytes, or [`PARALLEL_CHUNK_SIZE`] bytes if the
+/// algorithm hashes on several threads, so memory use does not depend on the amount of input.
+/// The exception are the git blob algorithms, which need the length of the input before hashing
+/// it, so the input is read into memory first. [`calculate_digest`] takes the length of a file
+/// from its metadata instead.
+pub fn hash_reader<R: Read>(alg: &mut Algorithm, reader: R) -> io::Result<Vec<u8>> {
+    hash_reader_with_progress(alg, reader, |_| {})
+}
+
+/// Calculates the digest of everything read from `reader`, calling `progress` with each chunk
+/// after it has been fed to the digest algorithm.
+pub fn hash_reader_with_progress<R, F>(alg: &mut Algorithm, reader: R, progress: F)
+    -> io::Result<Vec<u8>>
+where
+    R: Read,
+    F: FnMut(&[u8]),
+{
+    let mut digests = hash_reader_all(std::slice::from_mut(alg), reader, None, progress)?;
+    Ok(digests.remove(0))
+}
+
+/// Calculates the digests of everything read from `reader` with each of `algs` in a single pass
+/// over the input, calling `progress` with each chunk after it has been fed to the algorithms.
+///
+/// If `length` is `None` and any of `algs` hashes the input as a git blob object, the input is
+/// read into memory first to find its length.
+pub(crate) fn hash_reader_all<R, F>(algs: &mut [Algorithm], mut reader: R, length: Option<u64>,
+    mut progress: F) -> io::Result<Vec<Vec<u8>>>
+where
+    R: Read,
+    F: FnMut(&[u8]),
+{
+    let mut buffered = Vec::new();
+    let length = match length {
+        Some(length) => length,
+        None if algs.iter().any(|alg| alg.blob) => reader.read_to_end(&mut buffered)? as u64,
+        None => 0,
+    };
+    let mut reader = io::Cursor::new(buffered).chain(reader);
+
+    let chunk_size = match algs.iter().any(|alg| alg.parallel.is_some()) {
+        true => PARALLEL_CHUNK_SIZE,
+        false => CHUNK_SIZE,
+    };
+    let mut buffer = vec![0; chunk_size];
+    algs.iter_mut().for_each(|alg| alg.reset());
+    let header = format!("blob {}\0", length);
+    algs.iter_mut().filter(|alg| alg.blob).for_each(|alg| alg.update(header.as_bytes()));
+    let mut total = 0;
+
+    loop {
+        let n = read_full(&mut reader, &mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        algs.iter_mut().for_each(|alg| alg.update(&buffer[..n]));
+        progress(&buffer[..n]);
+        total += n as u64;
+    }
+    if total != length && algs.iter().any(|alg| alg.blob) {
+        return Err(io::Error::other("input changed size while hashing"));
+    }
+
+    Ok(algs.iter_mut().map(|alg| alg.finalize_reset()).collect())
+}
+
+/// Reads from `reader` until `buffer` is full or the end of input is reached.
+pub(crate) fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buffer.len() {
+        match reader.read(&mut buffer[n..]) {
+            Ok(0) => break,
+            Ok(m) => n += m,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(n)
+}
+
+/// Calculates the digest of the content of the file at `path` as a lowercase hexadecimal string.
+pub fn calculate_digest(alg: &mut Algorithm, path: &Path) -> io::Result<String> {
+    calculate_digest_with_progress(alg, path, |_| {})
+}
+
+/// Calculates the digest of the content of the file at `path` as a lowercase hexadecimal string,
+/// calling `progress` with each chunk read from the file.
+pub fn calculate_digest_with_progress<F>(alg: &mut Algorithm, path: &Path, progress: F)
+    -> io::Result<String>
+where
+    F: FnMut(&[u8]),
+{
+    Ok(hex::encode(hash_file_with_progress(alg, path, progress)?))
+}
+
+/// Calculates the digest of the content of the file at `path`, calling `progress` with each chunk
+/// read from the file.
+pub(crate) fn hash_file_with_progress<F>(alg: &mut Algorithm, path: &Path, progress: F)
+    -> io::Result<Vec<u8>>
+where
+    F: FnMut(&[u8]),
+{
+    let file = fs::File::open(path)?;
+    let length = file.metadata()?.len();
+    let mut digests = hash_reader_all(std::slice::from_mut(alg), file, Some(length), progress)?;
+    Ok(digests.remove(0))
+}
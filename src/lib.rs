@@ -0,0 +1,92 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+//! Library interface of the `shamv` utility.
+//!
+//! The `shamv` utility renames files to a destination path that is formed from the cryptographic
+//! hash of their content. This crate exposes the building blocks of the utility so that other
+//! tools can derive the same names without shelling out:
+//!
+//!  - [`expand_paths`] finds the files to process in directory operands,
+//!  - [`Algorithm`] and [`get_digest_alg`] select the digest algorithm,
+//!  - [`hash_reader`] and [`calculate_digest`] hash a reader or a file,
+//!  - [`Encoding`] encodes digests as hexadecimal, base32, base64url, base58 or Nix's base32,
+//!    optionally as self-describing multihashes,
+//!  - [`detect_extension`] recognizes the type of a file from its first bytes,
+//!  - [`destination_name`] and [`destination_path`] turn an encoded digest and the original path
+//!    into the destination name, or [`destination_name_with_head`] and
+//!    [`destination_path_with_head`] if the first bytes of the file are already read,
+//!  - [`Shard`] places destinations in subdirectories named by the leading characters of the
+//!    hash,
+//!  - [`Plan`] collects the renames for a set of files and carries them out without losing
+//!    existing files at the destination paths, and
+//!  - [`verify_path`] checks that the name of a file still matches its content.
+//!
+//! ```no_run
+//! use shamv::{Algorithm, Clobber, Mode, Naming, Plan};
+//!
+//! let mut alg = Algorithm::new("sha256")?;
+//! let plan = Plan::new(&mut alg, &Naming::default(), ["screenshot.png"])?;
+//! plan.execute(Mode::Rename, Clobber::Verify)?;
+//! # Ok::<(), shamv::Error>(())
+//! ```
+extern crate digest;
+extern crate sha2;
+extern crate sha3;
+extern crate blake2;
+extern crate blake3;
+extern crate sha1;
+#[cfg(feature = "legacy-hashes")]
+extern crate md5;
+extern crate rayon_core;
+extern crate hex;
+extern crate data_encoding;
+extern crate bs58;
+extern crate walkdir;
+extern crate chrono;
+
+mod cid;
+mod encoding;
+mod error;
+mod hash;
+mod magic;
+mod multihash;
+mod name;
+mod plan;
+mod shard;
+mod template;
+mod verify;
+mod walk;
+
+pub use encoding::{Encoding, ParseEncodingError};
+pub use error::{Error, Result};
+pub use hash::{ALGORITHMS, Algorithm, CHUNK_SIZE, PARALLEL_CHUNK_SIZE, calculate_digest,
+    calculate_digest_with_progress, get_digest_alg, hash_reader, hash_reader_with_progress,
+    is_legacy_alg, is_weak_alg};
+pub use magic::{MAGIC_LEN, detect_extension};
+pub use name::{EXTENSION_ALIASES, ExtPolicy, ExtensionAliases, KNOWN_EXTENSIONS, Naming,
+    ParseExtPolicyError, ParseExtensionAliasesError, ParseSuffixPolicyError, SuffixPolicy,
+    destination_name, destination_name_with_head, destination_path, destination_path_with_head,
+    suffixes};
+pub use plan::{Clobber, Entry, LinkTarget, Mode, Outcome, Plan};
+pub use shard::{ParseShardError, Shard, prune};
+pub use template::{Fields, ParseTemplateError, Template};
+pub use verify::{Verdict, verify_path, verify_placement};
+pub use walk::{Walk, expand_paths, expand_paths_keep_going};
+
+/// Exit status of the `shamv` utility.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    Success = 0,
+    InsufficientArgs,
+    UnsupportedAlg,
+    FileNotFound,
+    DigestError,
+    DestinationExists,
+    Mismatch,
+    NotAHashName,
+    RenameError,
+    PartialFailure,
+    UsageError,
+}
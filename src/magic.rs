@@ -0,0 +1,114 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::hash::read_full;
+
+/// The number of bytes at the start of a file that [`detect_extension`] looks at.
+pub const MAGIC_LEN: usize = 512;
+
+/// A file type recognized by the magic bytes at the start of its content.
+struct Magic {
+    /// The usual extension of files of the type.
+    extension: &'static str,
+    /// Other extensions of files of the type, or of formats that are based on it, e.g. `docx` for
+    /// ZIP archives.
+    aliases: &'static [&'static str],
+    /// Returns whether a file that starts with the given bytes is of the type.
+    matches: fn(&[u8]) -> bool,
+}
+
+/// Extensions of files in the ISO base media file format, which share their magic bytes.
+const ISO_MEDIA: &[&str] = &["mp4", "m4a", "m4b", "m4p", "m4v", "mov", "3gp", "3g2", "f4v"];
+
+/// Returns whether `head` starts an ISO base media file with one of `brands`, or with any brand if
+/// `brands` is empty.
+fn ftyp(head: &[u8], brands: &[&[u8]]) -> bool {
+    head.get(4..8) == Some(b"ftyp")
+        && head.get(8..12).is_some_and(|brand| brands.is_empty() || brands.contains(&brand))
+}
+
+/// Returns whether `head` starts a RIFF file of the form `form`.
+fn riff(head: &[u8], form: &[u8]) -> bool {
+    head.starts_with(b"RIFF") && head.get(8..12) == Some(form)
+}
+
+/// The recognized file types, in the order they are tried.
+const MAGIC: &[Magic] = &[
+    Magic { extension: "png", aliases: &[],
+        matches: |head| head.starts_with(b"\x89PNG\r\n\x1a\n") },
+    Magic { extension: "jpg", aliases: &["jpeg", "jpe", "jfif"],
+        matches: |head| head.starts_with(b"\xff\xd8\xff") },
+    Magic { extension: "gif", aliases: &[],
+        matches: |head| head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") },
+    Magic { extension: "webp", aliases: &[], matches: |head| riff(head, b"WEBP") },
+    Magic { extension: "wav", aliases: &[], matches: |head| riff(head, b"WAVE") },
+    Magic { extension: "avi", aliases: &[], matches: |head| riff(head, b"AVI ") },
+    Magic { extension: "tif", aliases: &["tiff", "dng", "cr2", "nef", "arw", "pef"],
+        matches: |head| head.starts_with(b"II*\0") || head.starts_with(b"MM\0*") },
+    Magic { extension: "heic", aliases: &["heif"],
+        matches: |head| ftyp(head, &[b"heic", b"heix", b"heim", b"heis", b"hevc", b"mif1"]) },
+    Magic { extension: "avif", aliases: &[], matches: |head| ftyp(head, &[b"avif", b"avis"]) },
+    Magic { extension: "mov", aliases: ISO_MEDIA, matches: |head| ftyp(head, &[b"qt  "]) },
+    Magic { extension: "m4a", aliases: ISO_MEDIA, matches: |head| ftyp(head, &[b"M4A "]) },
+    Magic { extension: "mp4", aliases: ISO_MEDIA, matches: |head| ftyp(head, &[]) },
+    Magic { extension: "webm", aliases: &["mkv", "mka", "mk3d"],
+        matches: |head| head.starts_with(b"\x1a\x45\xdf\xa3")
+            && head[..head.len().min(64)].windows(4).any(|window| window == b"webm") },
+    Magic { extension: "mkv", aliases: &["webm", "mka", "mk3d"],
+        matches: |head| head.starts_with(b"\x1a\x45\xdf\xa3") },
+    Magic { extension: "ogg", aliases: &["oga", "ogv", "opus", "spx"],
+        matches: |head| head.starts_with(b"OggS") },
+    Magic { extension: "flac", aliases: &[], matches: |head| head.starts_with(b"fLaC") },
+    Magic { extension: "mp3", aliases: &[], matches: |head| head.starts_with(b"ID3") },
+    Magic { extension: "pdf", aliases: &["ai"], matches: |head| head.starts_with(b"%PDF-") },
+    Magic { extension: "psd", aliases: &[], matches: |head| head.starts_with(b"8BPS") },
+    Magic { extension: "rtf", aliases: &[], matches: |head| head.starts_with(b"{\\rtf") },
+    Magic { extension: "zip",
+        aliases: &["docx", "xlsx", "pptx", "odt", "ods", "odp", "epub", "jar", "war", "apk", "xpi",
+            "whl", "cbz", "kmz", "3mf", "nupkg"],
+        matches: |head| head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") },
+    Magic { extension: "gz", aliases: &["tgz", "svgz"],
+        matches: |head| head.starts_with(b"\x1f\x8b") },
+    Magic { extension: "bz2", aliases: &["tbz", "tbz2"], matches: |head| head.starts_with(b"BZh") },
+    Magic { extension: "xz", aliases: &["txz"], matches: |head| head.starts_with(b"\xfd7zXZ\0") },
+    Magic { extension: "zst", aliases: &["tzst"],
+        matches: |head| head.starts_with(b"\x28\xb5\x2f\xfd") },
+    Magic { extension: "7z", aliases: &[],
+        matches: |head| head.starts_with(b"7z\xbc\xaf\x27\x1c") },
+    Magic { extension: "tar", aliases: &[], matches: |head| head.get(257..262) == Some(b"ustar") },
+    Magic { extension: "sqlite", aliases: &["sqlite3", "db"],
+        matches: |head| head.starts_with(b"SQLite format 3\0") },
+    Magic { extension: "wasm", aliases: &[], matches: |head| head.starts_with(b"\0asm") },
+    Magic { extension: "woff", aliases: &[], matches: |head| head.starts_with(b"wOFF") },
+    Magic { extension: "woff2", aliases: &[], matches: |head| head.starts_with(b"wOF2") },
+    Magic { extension: "otf", aliases: &[], matches: |head| head.starts_with(b"OTTO") },
+];
+
+/// Returns the usual extension of the type of a file that starts with `head`, e.g. `png` for a
+/// PNG image, or `None` if the type is not recognized. At most [`MAGIC_LEN`] bytes of `head` are
+/// looked at.
+pub fn detect_extension(head: &[u8]) -> Option<&'static str> {
+    let head = &head[..head.len().min(MAGIC_LEN)];
+    MAGIC.iter().find(|magic| (magic.matches)(head)).map(|magic| magic.extension)
+}
+
+/// Returns whether `suffix` is an extension of files of the type detected as `extension`,
+/// ignoring case.
+pub(crate) fn is_extension_of(suffix: &str, extension: &str) -> bool {
+    suffix.eq_ignore_ascii_case(extension) || MAGIC.iter()
+        .filter(|magic| magic.extension == extension)
+        .any(|magic| magic.aliases.iter().any(|alias| suffix.eq_ignore_ascii_case(alias)))
+}
+
+/// Reads the first [`MAGIC_LEN`] bytes of the file at `path`.
+pub(crate) fn read_head(path: &Path) -> io::Result<Vec<u8>> {
+    let mut head = vec![0; MAGIC_LEN];
+    let n = read_full(&mut fs::File::open(path)?, &mut head)?;
+    head.truncate(n);
+    Ok(head)
+}
@@ -3,73 +3,338 @@
  * SPDX-License-Identifier: Apache-2.0 or MIT
  */
 extern crate getopts;
-extern crate digest;
-extern crate sha2;
-extern crate hex;
+extern crate shamv;
 
 use std::env;
 use std::fs;
-use std::io::Read;
-use std::path::{Path,PathBuf};
-use getopts::Options;
-use digest::DynDigest;
-
-enum Mode {
-    Rename,
-    DryRun,
-}
-
-#[derive(Copy, Clone)]
-enum Status {
-    Success = 0,
-    InsufficientArgs,
-    UnsupportedAlg,
-    FileNotFound,
-    DigestError,
-}
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use getopts::{Matches, Options};
+use shamv::{Algorithm, Clobber, Encoding, Entry, Error, ExtPolicy, ExtensionAliases, LinkTarget, Mode,
+    Naming, Outcome, Plan, Shard, Status, Template, Verdict, Walk};
 
 fn print_usage() {
     print!(r#"
 Usage: {0} [OPTION...] FILE[...]
+       {0} --check [OPTION...] FILE[...]
+       {0} --reshard [--shard LEVELS] [OPTION...] DIR[...]
 The {0} utility renames the file named by the FILE operand to a destination path that is formed
-from the SHA-2 hash of the content of the file.
+from the hash of the content of the file. Alternatively, the file can be copied or linked to from
+the destination path, leaving the original in place.
 
 If the FILE operand includes a filename extension that consists of one or more suffixes, each
-separated by a dot (.) character, the destination path is formed by the concatenation of the SHA-2
-hash and the filename extension suffixes of the original FILE name.
+separated by a dot (.) character, the destination path is formed by the concatenation of the hash
+and the filename extension suffixes of the original FILE name. A suffix consists of ASCII
+letters and digits and contains at least one letter, so e.g. `v1.2 notes.txt` has only the suffix
+`txt`. Leading dots are not suffix separators, so e.g. `.bashrc` has no suffixes.
+
+If the destination path already exists, its content is compared to the content of the FILE. If the
+content is identical, the FILE is skipped as a duplicate, otherwise the FILE is not renamed and
+{0} exits with an error, unless another behavior is selected with --force, --interactive or
+--no-clobber.
+
+With --check, {0} instead verifies that the name of each FILE is the hash of its content and
+reports OK, MISMATCH or NOT-A-HASH-NAME for each FILE. If the name is a CID or a multihash, the
+file is hashed with the algorithm it names. Otherwise, unless an algorithm is given, the file is
+hashed with every algorithm that produces hashes of the length in the name, in any encoding
+unless an encoding is given, and OK is followed by the algorithm that matches. With --shard, a
+FILE whose name matches but that is not in the subdirectories for its name is reported as
+MISPLACED. The exit status is zero only if the name of every FILE matches.
+
+With --reshard, {0} instead moves the hash-named files below each DIR to the subdirectories of
+the layout given with --shard, or directly into DIR without --shard, and removes the directories
+that are left empty. The files are not hashed. Names shortened with --length are moved if they
+are in subdirectories named by their leading characters.
 
 Mandatory arguments to long options are mandatory for short options too.
- -a, --algorithm      The SHA-2 algorithm to use: sha224, sha256 (default), sha384, or sha512.
+ -a, --algorithm      The hash algorithm to use: sha224, sha256 (default), sha384, sha512,
+                      sha512-224, sha512-256, sha3-224, sha3-256, sha3-384, sha3-512, shake128,
+                      shake256, blake2b, blake2s, blake3, git-sha1 or git-sha256. The output
+                      length of shake128, shake256, blake2b and blake2s in bits can be given as
+                      a suffix, e.g. blake2b-160. With git-sha1 and git-sha256, the hash is the
+                      blob object ID that `git hash-object` gives the file. If built with the
+                      legacy-hashes feature, md5 and sha1 are also available for existing
+                      names. Files are renamed with md5 and sha1 only with --allow-weak.
+     --allow-weak     Allow renaming files with algorithms that are vulnerable to collisions.
+ -j, --threads        The number of threads to hash each file on with blake3 (default 1).
+                      The hash does not depend on the number of threads.
+     --check          Verify that the names of the files match their content.
+ -c, --copy           Copy the file to the destination path instead of renaming it.
+ -l, --link           Create a hard link to the file at the destination path instead of
+                      renaming it. Fails if the destination path is on another filesystem.
+     --symlink        Create a symbolic link to the file at the destination path instead of
+                      renaming it.
+     --relative       Make the target of symbolic links relative to the destination path
+                      instead of absolute.
  -n, --dry-run        Display the current and new filenames but do not perform the rename.
+ -s, --suffixes       Which suffixes to keep: all (default), last, none, known, or a number N
+                      to keep at most N suffixes. With known, the longest known compound
+                      extension such as tar.gz or user.js is kept, or else the last suffix.
+     --ext            Check the suffixes against the type of the file detected from its first
+                      bytes, e.g. PNG, JPEG, GIF, WebP, PDF, ZIP, gzip or MP4: keep (default)
+                      the suffixes as they are, detect to add the extension of the type to a
+                      name without suffixes, or fix to also replace a last suffix that is not
+                      an extension of the type.
+     --normalize-ext  Lowercase the suffixes and replace aliases such as jpeg, tiff and htm
+                      with jpg, tif and html, so that files with identical content get the
+                      same name. Each line of $XDG_CONFIG_HOME/{0}/extensions, or
+                      ~/.config/{0}/extensions, can add an extension followed by its aliases,
+                      e.g. `yaml yml`.
+ -e, --encoding       How the hash is encoded in the name: hex (default), base32, base64url,
+                      base58 or nix32. Names in base64url and base58 are mixed case, so two
+                      names may differ only in case, which is detected as a collision on
+                      case-insensitive filesystems.
+     --cid            Name the file by the CIDv1 that `ipfs add --cid-version=1 --raw-leaves`
+                      gives it, e.g. bafkrei followed by the rest of the base32 encoded CID.
+                      The CID is calculated without connecting to IPFS.
+     --multihash      Encode the hash as a multihash with a multibase prefix, so that the name
+                      also identifies the algorithm, e.g. f1220 followed by the hexadecimal
+                      SHA-256 hash. The nix32 encoding has no multibase prefix, and git-sha1
+                      and git-sha256 have no multihash code.
+     --length         Keep only the given number of characters of the hash in the name. More
+                      characters are kept where needed to tell apart files with different
+                      content in the same directory, including existing files, ignoring case
+                      with base64url and base58.
+     --format         Form the destination name from a template instead of {{hash}}.{{exts}}.
+                      The placeholders are {{hash}} or {{hash:N}} for the first N characters of
+                      the hash, {{algo}}, {{stem}}, {{ext}} for the last suffix, {{exts}} for the
+                      suffixes selected by --suffixes, {{size}} in bytes, and {{mtime}} or e.g.
+                      {{mtime:%Y-%m-%d}} for the modification time. Every template must include
+                      the full {{hash}}. Directories separated by / are created as needed.
+ -t, --target-directory
+                      Place the destination paths in the given directory instead of the
+                      directory of each FILE. The same checks for existing destination paths
+                      apply.
+     --parents        Create the target directory and its parents if they do not exist.
+     --shard          Place the destination in subdirectories named by the leading characters
+                      of the hash, e.g. 2 for ab/ab12cd... or 2/2 for ab/12/ab12cd... like
+                      .git/objects. A file that already is in the subdirectories for its name
+                      stays in them.
+     --reshard        Move the hash-named files below directories to the layout given with
+                      --shard.
+ -f, --force          Replace existing destination paths.
+ -i, --interactive    Prompt before replacing existing destination paths with different
+                      content.
+     --no-clobber     Skip files whose destination path exists.
+ -r, --recursive      Process the regular files in directories named by FILE operands and their
+                      subdirectories.
+     --follow-symlinks
+                      Follow symbolic links found in directories.
+ -x, --one-file-system
+                      Do not descend into directories on other filesystems.
+     --max-depth      Descend at most the given number of levels below directories named by
+                      FILE operands.
+ -k, --keep-going     Process all files even if some of them cannot be processed. The exit
+                      status indicates a partial failure if some but not all files failed.
+ -p, --progress       Display the progress of hashing each file on standard error.
  -h, --help           Print this help and exit.
  -V, --version        Print the version of the program and exit."#,
     env!("CARGO_BIN_NAME"));
 }
 
+/// Reports a command-line usage error on standard error and exits.
+fn usage_error(message: &str) -> ! {
+    eprintln!("{0}: {1}", env!("CARGO_BIN_NAME"), message);
+    eprintln!("Try '{0} --help' for more information.", env!("CARGO_BIN_NAME"));
+    std::process::exit(Status::UsageError as i32);
+}
+
+/// Reports a usage error if more than one of the long options `names` is present.
+fn check_conflicts(matches: &Matches, names: &[&str]) {
+    let present: Vec<String> = names.iter()
+        .filter(|name| matches.opt_present(name))
+        .map(|name| format!("--{}", name))
+        .collect();
+    if present.len() > 1 {
+        usage_error(&format!("conflicting options {}", present.join(" and ")));
+    }
+}
+
+/// Reports a usage error if the long option `name` is present without the long option `required`.
+fn check_requires(matches: &Matches, name: &str, required: &str) {
+    if matches.opt_present(name) && !matches.opt_present(required) {
+        usage_error(&format!("option --{} requires --{}", name, required));
+    }
+}
+
+/// Returns the path of the file that adds aliases to those of --normalize-ext.
+fn extensions_config() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config.join(env!("CARGO_BIN_NAME")).join("extensions"))
+}
+
+/// Returns the built-in extension aliases together with those of the configuration file.
+fn extension_aliases() -> ExtensionAliases {
+    let mut aliases = ExtensionAliases::default();
+    if let Some(path) = extensions_config() {
+        let result = match fs::read_to_string(&path) {
+            Ok(config) => aliases.read_config(&config).map_err(|e| e.to_string()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(message) = result {
+            usage_error(&format!("{}: {}", path.display(), message));
+        }
+    }
+    aliases
+}
+
 fn print_version() {
     println!("{} version {}", env!("CARGO_BIN_NAME"), env!("CARGO_PKG_VERSION"));
     std::process::exit(0);
 }
 
-fn get_digest_alg(s: &str) -> Option<Box<dyn DynDigest>>{
-    match s {
-              "" => Some(Box::new(sha2::Sha256::default())),  // Default digest algorithm
-        "sha224" => Some(Box::new(sha2::Sha224::default())),
-        "sha256" => Some(Box::new(sha2::Sha256::default())),
-        "sha384" => Some(Box::new(sha2::Sha384::default())),
-        "sha512" => Some(Box::new(sha2::Sha512::default())),
-               _ => None,  // Unsupported digest
+/// Displays the progress of hashing files on standard error.
+#[derive(Default)]
+struct Progress {
+    path: PathBuf,
+    total: u64,
+    done: u64,
+}
+
+impl Progress {
+    fn update(&mut self, path: &Path, chunk: &[u8]) {
+        if self.path != path {
+            self.path = path.to_path_buf();
+            self.total = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            self.done = 0;
+        }
+        self.done += chunk.len() as u64;
+
+        let percent = (self.done.min(self.total) * 100).checked_div(self.total).unwrap_or(100);
+        eprint!("\r{0}: hashing {1:?}: {2}%", env!("CARGO_BIN_NAME"),
+            path.file_name().unwrap_or(path.as_os_str()), percent);
+        if self.done >= self.total {
+            eprintln!();
+        }
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// Tracks the files that failed to be processed and the resulting exit status.
+struct Report {
+    keep_going: bool,
+    succeeded: usize,
+    failed: usize,
+    status: Status,
+}
+
+impl Report {
+    fn new(keep_going: bool) -> Report {
+        Report { keep_going, succeeded: 0, failed: 0, status: Status::Success }
+    }
+
+    /// Reports `err` on standard error and records the exit status of the first error.
+    fn fail(&mut self, err: Error) {
+        eprintln!("{0}: {1}", env!("CARGO_BIN_NAME"), err);
+        if self.failed == 0 {
+            self.status = err.status();
+        }
+        self.failed += 1;
+    }
+
+    fn fail_all(&mut self, errors: Vec<Error>) {
+        errors.into_iter().for_each(|err| self.fail(err));
+    }
+
+    /// Returns whether processing should stop at the first failed file, i.e., without
+    /// --keep-going.
+    fn stop(&self) -> bool {
+        self.failed > 0 && !self.keep_going
+    }
+
+    /// Returns the exit status, which is never success if any file failed.
+    fn status(&self) -> Status {
+        if self.failed > 0 && self.succeeded > 0 && self.keep_going {
+            Status::PartialFailure
+        } else {
+            self.status
+        }
+    }
+}
+
+/// Asks on standard error whether to replace the destination of `entry`.
+fn confirm_overwrite(entry: &Entry) -> bool {
+    eprint!("{0}: overwrite {1:?}? ", env!("CARGO_BIN_NAME"), entry.destination);
+    let mut answer = String::new();
+    match std::io::stdin().lock().read_line(&mut answer) {
+        Ok(_) => answer.trim_start().starts_with(['y', 'Y']),
+        Err(_) => false,
+    }
+}
+
+/// Verifies that the names of the files at `paths` match their content and that the files are in
+/// the subdirectories of `shard` for their names.
+fn check(paths: &[PathBuf], shard: &Shard, algorithm: Option<&str>, encoding: Option<Encoding>)
+    -> Status
+{
+    let mut status = Status::Success;
+
+    for p in paths {
+        match shamv::verify_placement(p, shard, algorithm, encoding) {
+            Ok(Verdict::Ok(alg)) => println!("{0}: OK ({1})", p.display(), alg),
+            Ok(Verdict::Mismatch) => {
+                println!("{0}: MISMATCH", p.display());
+                status = Status::Mismatch;
+            },
+            Ok(Verdict::Misplaced(alg)) => {
+                println!("{0}: MISPLACED ({1})", p.display(), alg);
+                status = Status::Mismatch;
+            },
+            Ok(Verdict::NotAHashName) => {
+                println!("{0}: NOT-A-HASH-NAME", p.display());
+                if status == Status::Success {
+                    status = Status::NotAHashName;
+                }
+            },
+            Err(err) => {
+                eprintln!("{0}: {1}", env!("CARGO_BIN_NAME"), err);
+                if status != Status::Mismatch {
+                    status = err.status();
+                }
+            },
+        }
     }
+
+    status
 }
 
-fn calculate_digest(alg: &mut dyn DynDigest, path: &Path) -> Result<String, std::io::Error> {
-    let mut file = fs::File::open(path)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
+/// Applies `mode` to every entry of `plan`, recording the results in `report`.
+fn apply(plan: &Plan, mode: Mode, clobber: Clobber, interactive: bool, show_length: bool,
+    report: &mut Report)
+{
+    for entry in plan.entries() {
+        if mode == Mode::DryRun {
+            match show_length {
+                true => println!("{:?} → {:?} (length {})",
+                    entry.source, entry.destination, entry.length),
+                false => println!("{:?} → {:?}", entry.source, entry.destination),
+            }
+        }
+
+        // Destinations with identical content are skipped without asking
+        let mut result = entry.apply(mode, clobber);
+        if interactive && matches!(result, Err(Error::DestinationExists { .. })) {
+            result = match confirm_overwrite(entry) {
+                true => entry.apply(mode, Clobber::Force),
+                false => Ok(Outcome::Exists),
+            };
+        }
 
-    alg.update(&buffer);
-    let d = alg.finalize_reset();
-    Ok(hex::encode(d))
+        match result {
+            Ok(Outcome::Identical) => {
+                eprintln!("{0}: skipping {1:?}: {2:?} has identical content",
+                    env!("CARGO_BIN_NAME"), entry.source, entry.destination);
+                report.succeeded += 1;
+            },
+            Ok(_) => report.succeeded += 1,
+            Err(err) => report.fail(err),
+        }
+        if report.stop() {
+            break;
+        }
+    }
 }
 
 fn main() {
@@ -77,16 +342,73 @@ fn main() {
     let args: Vec<String> = env::args().collect();
 
     let mut opts = Options::new();
-    opts.optopt("a", "algorithm", "The SHA-2 algorithm to use: sha224, sha256 (default), sha384, or sha512", "sha256");
+    opts.optopt("a", "algorithm", "The hash algorithm to use, e.g. sha256 (default) or sha3-256", "sha256");
+    opts.optflag("", "allow-weak", "Allow renaming files with algorithms that are vulnerable to collisions");
+    opts.optopt("j", "threads", "The number of threads to hash each file on with blake3", "N");
+    opts.optflag("", "check", "Verify that the names of the files match their content");
+    opts.optflag("c", "copy", "Copy the file to the destination path instead of renaming it");
+    opts.optflag("l", "link", "Create a hard link to the file at the destination path instead of renaming it");
+    opts.optflag("", "symlink", "Create a symbolic link to the file at the destination path instead of renaming it");
+    opts.optflag("", "relative", "Make the target of symbolic links relative to the destination path");
     opts.optflag("n", "dry-run", "Display the original and new filenames but do not perform the rename");
+    opts.optopt("s", "suffixes", "Which suffixes to keep: all (default), last, none, known, or a number N", "all");
+    opts.optopt("", "ext", "Check the suffixes against the type of the file: keep (default), detect or fix", "keep");
+    opts.optflag("", "normalize-ext", "Lowercase the suffixes and replace aliases such as jpeg with jpg");
+    opts.optopt("e", "encoding", "How the hash is encoded in the name: hex (default), base32, base64url, base58 or nix32", "hex");
+    opts.optflag("", "cid", "Name the file by its IPFS CIDv1 instead of its hash");
+    opts.optflag("", "multihash", "Encode the hash as a multihash with a multibase prefix");
+    opts.optopt("", "format", "Form the destination name from a template", "TEMPLATE");
+    opts.optopt("t", "target-directory", "Place the destination paths in the given directory", "DIR");
+    opts.optflag("", "parents", "Create the target directory and its parents if they do not exist");
+    opts.optopt("", "shard", "Place the destination in subdirectories named by the leading characters of the hash", "LEVELS");
+    opts.optflag("", "reshard", "Move the hash-named files below directories to the layout given with --shard");
+    opts.optopt("", "length", "Keep only the given number of characters of the hash in the name", "N");
+    opts.optflag("f", "force", "Replace existing destination paths");
+    opts.optflag("i", "interactive", "Prompt before replacing existing destination paths");
+    opts.optflag("", "no-clobber", "Skip files whose destination path exists");
+    opts.optflag("r", "recursive", "Process the regular files in directories and their subdirectories");
+    opts.optflag("", "follow-symlinks", "Follow symbolic links found in directories");
+    opts.optflag("x", "one-file-system", "Do not descend into directories on other filesystems");
+    opts.optopt("", "max-depth", "Descend at most the given number of levels below directories", "N");
+    opts.optflag("k", "keep-going", "Process all files even if some of them cannot be processed");
+    opts.optflag("p", "progress", "Display the progress of hashing each file on standard error");
     opts.optflag("h", "help", "Print this help and exit");
     opts.optflag("V", "version", "Print the version of the program and exit");
 
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => m,
-        Err(e) => { panic!("{}", e.to_string()) }
+        Err(e) => usage_error(&e.to_string()),
     };
 
+    check_conflicts(&matches, &["check", "copy", "link", "symlink"]);
+    check_conflicts(&matches, &["check", "dry-run"]);
+    check_conflicts(&matches, &["check", "length"]);
+    check_conflicts(&matches, &["check", "format"]);
+    check_conflicts(&matches, &["check", "multihash"]);
+    check_conflicts(&matches, &["multihash", "length"]);
+    for name in ["algorithm", "multihash", "length"] {
+        check_conflicts(&matches, &["cid", name]);
+    }
+    for name in ["cid", "multihash", "format"] {
+        check_conflicts(&matches, &["shard", name]);
+    }
+    for name in ["check", "copy", "link", "symlink", "algorithm", "encoding", "cid", "multihash",
+        "length", "format", "suffixes", "recursive"]
+    {
+        check_conflicts(&matches, &["reshard", name]);
+    }
+    check_conflicts(&matches, &["check", "force", "interactive", "no-clobber"]);
+    for name in ["check", "reshard"] {
+        check_conflicts(&matches, &["target-directory", name]);
+        check_conflicts(&matches, &["normalize-ext", name]);
+        check_conflicts(&matches, &["ext", name]);
+    }
+    check_requires(&matches, "relative", "symlink");
+    check_requires(&matches, "parents", "target-directory");
+    for name in ["follow-symlinks", "one-file-system", "max-depth"] {
+        check_requires(&matches, name, "recursive");
+    }
+
     if matches.opt_present("h") {
         print_usage();
         std::process::exit(Status::Success as i32);
@@ -97,21 +419,116 @@ fn main() {
         std::process::exit(Status::Success as i32);
     }
 
-    let mut boxed_alg = if let Some(a) = get_digest_alg(&matches.opt_str("a").unwrap_or_default()) {
-        a
-    } else {
-        eprintln!("{0}: unsupported algorithm {1}",
-            env!("CARGO_BIN_NAME"),
-            &matches.opt_str("a").unwrap());
-        std::process::exit(Status::UnsupportedAlg as i32);
+    let threads = match matches.opt_str("j").map(|s| s.parse::<usize>()) {
+        Some(Ok(threads)) if threads > 0 => threads,
+        Some(_) => usage_error(&format!("invalid number of threads {}", matches.opt_str("j").unwrap())),
+        None => 1,
+    };
+
+    let algorithm = match matches.opt_present("cid") {
+        true => Some("cid".to_string()),
+        false => matches.opt_str("a"),
+    };
+    let mut alg = match Algorithm::new(&algorithm.clone().unwrap_or_default()) {
+        Ok(a) => a.with_threads(threads),
+        Err(err) => {
+            eprintln!("{0}: {1}", env!("CARGO_BIN_NAME"), err);
+            std::process::exit(err.status() as i32);
+        }
     };
 
     let mode = if matches.opt_present("n") {
         Mode::DryRun
+    } else if matches.opt_present("c") {
+        Mode::Copy
+    } else if matches.opt_present("l") {
+        Mode::HardLink
+    } else if matches.opt_present("symlink") {
+        Mode::Symlink(if matches.opt_present("relative") {
+            LinkTarget::Relative
+        } else {
+            LinkTarget::Absolute
+        })
     } else {
         Mode::Rename
     };
 
+    if alg.is_weak() {
+        eprintln!("{0}: warning: {1} is vulnerable to collisions, files with different content may get the same name",
+            env!("CARGO_BIN_NAME"), alg.name());
+        if alg.is_legacy() && mode == Mode::Rename && !matches.opt_present("check")
+            && !matches.opt_present("allow-weak") {
+            usage_error(&format!("refusing to rename files with {} without --allow-weak", alg.name()));
+        }
+    }
+
+    let interactive = matches.opt_present("i");
+    let clobber = if matches.opt_present("f") {
+        Clobber::Force
+    } else if matches.opt_present("no-clobber") {
+        Clobber::NoClobber
+    } else {
+        Clobber::Verify
+    };
+
+    let keep_going = matches.opt_present("k");
+    let show_progress = matches.opt_present("p");
+    let check_names = matches.opt_present("check");
+    let reshard = matches.opt_present("reshard");
+    let parents = matches.opt_present("parents");
+    let check_algorithm = algorithm;
+
+    let mut naming = Naming::default();
+    if let Some(s) = matches.opt_str("s") {
+        naming.suffixes = match s.parse() {
+            Ok(policy) => policy,
+            Err(e) => usage_error(&e.to_string()),
+        };
+    }
+    if let Some(s) = matches.opt_str("ext") {
+        naming.ext = match s.parse::<ExtPolicy>() {
+            Ok(policy) => policy,
+            Err(e) => usage_error(&e.to_string()),
+        };
+    }
+    if matches.opt_present("normalize-ext") {
+        naming.normalize_ext = Some(extension_aliases());
+    }
+    let check_encoding = matches.opt_str("e").map(|s| match s.parse::<Encoding>() {
+        Ok(encoding) => encoding,
+        Err(e) => usage_error(&e.to_string()),
+    });
+    naming.encoding = match (check_encoding, matches.opt_present("cid")) {
+        (Some(encoding), _) => encoding,
+        (None, true) => Encoding::Base32,
+        (None, false) => Encoding::Hex,
+    };
+    naming.multihash = matches.opt_present("multihash");
+    naming.format = matches.opt_str("format").map(|s| match s.parse::<Template>() {
+        Ok(template) => template,
+        Err(e) => usage_error(&e.to_string()),
+    });
+    naming.shard = match matches.opt_str("shard").map(|s| s.parse::<Shard>()) {
+        Some(Ok(shard)) => shard,
+        Some(Err(e)) => usage_error(&e.to_string()),
+        None => Shard::default(),
+    };
+    naming.target = matches.opt_str("t").map(PathBuf::from);
+    naming.length = matches.opt_str("length").map(|s| match s.parse::<usize>() {
+        Ok(length) if length > 0 => length,
+        _ => usage_error(&format!("invalid length {}", s)),
+    });
+
+    let walk = Walk {
+        recursive: matches.opt_present("r"),
+        follow_symlinks: matches.opt_present("follow-symlinks"),
+        one_file_system: matches.opt_present("x"),
+        max_depth: matches.opt_str("max-depth").map(|s| match s.parse::<usize>() {
+            Ok(depth) => depth,
+            Err(_) => usage_error(&format!("invalid depth {}", s)),
+        }),
+    };
+
     let pathnames: Vec<String> = if ! matches.free.is_empty() {
         matches.free
     } else {
@@ -120,51 +537,75 @@ fn main() {
         std::process::exit(Status::InsufficientArgs as i32);
     };
 
-    let mut paths: Vec<PathBuf> = Vec::with_capacity(pathnames.len());
-    let mut digests: Vec<String> = Vec::with_capacity(pathnames.len());
-
-    for p in pathnames {
-        let path = Path::new(&p);
-        if !path.exists() {
-            eprintln!("{0}: file not found {1}", env!("CARGO_BIN_NAME"), p);
-            std::process::exit(Status::FileNotFound as i32);
+    if reshard {
+        let mut report = Report::new(keep_going);
+        for root in pathnames.iter().map(Path::new) {
+            match Plan::reshard(root, &naming.shard) {
+                Ok(plan) => {
+                    apply(&plan, mode, clobber, interactive, false, &mut report);
+                    if mode != Mode::DryRun {
+                        plan.entries().iter().for_each(|entry| shamv::prune(&entry.source, root));
+                    }
+                },
+                Err(err) => report.fail(err),
+            }
+            if report.stop() {
+                break;
+            }
         }
-        paths.push(path.to_path_buf());
+        std::process::exit(report.status() as i32);
     }
 
-    for p in paths.as_slice() {
-        match calculate_digest(&mut *boxed_alg, &p) {
-            Ok(d) => digests.push(d),
-            Err(err) => {
-                eprintln!("{0}: error calculating digest for: {1:?}: {2}",
-                    env!("CARGO_BIN_NAME"),
-                    p.as_path().file_name().unwrap(),
-                    err);
-                std::process::exit(Status::DigestError as i32);
-            }
-        };
+    let (paths, errors) = if keep_going {
+        shamv::expand_paths_keep_going(&pathnames, &walk)
+    } else {
+        match shamv::expand_paths(&pathnames, &walk) {
+            Ok(paths) => (paths, Vec::new()),
+            Err(err) => (Vec::new(), vec![err]),
+        }
+    };
+    let mut report = Report::new(keep_going);
+    report.fail_all(errors);
+    if report.stop() {
+        std::process::exit(report.status() as i32);
     }
 
-    for i in paths.iter().zip(digests.iter()) {
-        let (path, digest) = i;
+    if check_names {
+        std::process::exit(check(&paths, &naming.shard, check_algorithm.as_deref(), check_encoding) as i32);
+    }
 
-        let new_file_name = if let Some(ext) = path.extension() {
-            format!("{}.{}", digest, ext.to_string_lossy())
-        } else {
-            format!("{}", digest)
+    // Like mv -t, the target directory must exist unless it is created with --parents
+    if let Some(target) = naming.target.as_ref().filter(|target| !target.is_dir()) {
+        let result = match (parents, mode) {
+            (true, Mode::DryRun) => Ok(()),
+            (true, _) => fs::create_dir_all(target),
+            (false, _) if target.exists() => Err(io::Error::from(io::ErrorKind::NotADirectory)),
+            (false, _) => Err(io::Error::from(io::ErrorKind::NotFound)),
         };
+        if let Err(source) = result {
+            report.fail(Error::TargetDirectory { path: target.clone(), source });
+            std::process::exit(report.status() as i32);
+        }
+    }
 
-        let new_path = path.with_file_name(new_file_name);
+    let mut progress = Progress::default();
+    let on_chunk = |path: &Path, chunk: &[u8]| if show_progress {
+        progress.update(path, chunk)
+    };
 
-        match mode {
-            Mode::DryRun => { println!("{:?} → {:?}", path, new_path); },
-            Mode::Rename => { if let Err(err) = fs::rename(&path, &new_path) {
-                    eprintln!("{0}: error renaming file {1:?}: {2}",
-                        env!("CARGO_BIN_NAME"),
-                        path.as_path().file_name().unwrap(),
-                        err);
-                }
-            },
+    let (plan, errors) = if keep_going {
+        Plan::keep_going(&mut alg, &naming, &paths, on_chunk)
+    } else {
+        match Plan::with_progress(&mut alg, &naming, &paths, on_chunk) {
+            Ok(plan) => (plan, Vec::new()),
+            Err(err) => (Plan::default(), vec![err]),
         }
+    };
+    report.fail_all(errors);
+    if report.stop() {
+        std::process::exit(report.status() as i32);
     }
+
+    apply(&plan, mode, clobber, interactive, naming.length.is_some(), &mut report);
+    std::process::exit(report.status() as i32);
 }
@@ -0,0 +1,113 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use crate::encoding::Encoding;
+
+/// Multihash codes of the algorithms with a fixed output size, from the multicodec table.
+const CODES: &[(&str, u64)] = &[
+    ("sha1", 0x11), ("sha256", 0x12), ("sha512", 0x13), ("sha3-512", 0x14), ("sha3-384", 0x15),
+    ("sha3-256", 0x16), ("sha3-224", 0x17), ("blake3", 0x1e), ("sha384", 0x20), ("md5", 0xd5),
+    ("sha224", 0x1013), ("sha512-224", 0x1014), ("sha512-256", 0x1015),
+];
+
+/// Multihash codes of SHAKE128 and SHAKE256, whose output size is given by the length field.
+const SHAKE128: u64 = 0x18;
+const SHAKE256: u64 = 0x19;
+
+/// Multihash codes of BLAKE2b and BLAKE2s with an output size of 8 bits, the codes of the larger
+/// output sizes follow in steps of 8 bits.
+const BLAKE2B_8: u64 = 0xb201;
+const BLAKE2S_8: u64 = 0xb241;
+
+/// Returns the multihash code of the algorithm named `name` with an output size of `size` bytes.
+pub fn code(name: &str, size: usize) -> Option<u64> {
+    let size = size as u64;
+    match name.split('-').next().unwrap_or_default() {
+        "shake128" => Some(SHAKE128),
+        "shake256" => Some(SHAKE256),
+         "blake2b" if (1..=64).contains(&size) => Some(BLAKE2B_8 + size - 1),
+         "blake2s" if (1..=32).contains(&size) => Some(BLAKE2S_8 + size - 1),
+                 _ => CODES.iter().find(|(alg, _)| *alg == name).map(|(_, code)| *code),
+    }
+}
+
+/// Returns the name of the algorithm with multihash `code` and an output size of `size` bytes
+/// as accepted by [`Algorithm::new`](crate::Algorithm::new), or `None` if it is not supported.
+pub fn algorithm(code: u64, size: usize) -> Option<String> {
+    let bits = size * 8;
+    match code {
+        SHAKE128 if size > 0 => Some(format!("shake128-{}", bits)),
+        SHAKE256 if size > 0 => Some(format!("shake256-{}", bits)),
+        BLAKE2B_8..=0xb240 if code - BLAKE2B_8 + 1 == size as u64 =>
+            Some(format!("blake2b-{}", bits)),
+        BLAKE2S_8..=0xb260 if code - BLAKE2S_8 + 1 == size as u64 =>
+            Some(format!("blake2s-{}", bits)),
+        _ => CODES.iter().find(|(_, c)| *c == code).map(|(alg, _)| alg.to_string()),
+    }
+}
+
+/// Appends `value` to `bytes` as an unsigned varint, which is also the encoding of protobuf
+/// varints.
+pub(crate) fn push_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Reads an unsigned varint from the start of `bytes`, returning it and the remaining bytes.
+fn read_varint(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+/// Returns the multihash of `digest` calculated with the algorithm named `name`, i.e., the
+/// multihash code of the algorithm and the size of the digest as varints followed by the digest.
+pub fn multihash(name: &str, digest: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(digest.len() + 4);
+    push_varint(&mut bytes, code(name, digest.len())?);
+    push_varint(&mut bytes, digest.len() as u64);
+    bytes.extend_from_slice(digest);
+    Some(bytes)
+}
+
+/// Encodes the multihash of `digest` calculated with the algorithm named `name` with `encoding`,
+/// prefixed with the multibase prefix of the encoding.
+pub fn encode(name: &str, digest: &[u8], encoding: Encoding) -> Option<String> {
+    let prefix = encoding.multibase()?;
+    Some(format!("{}{}", prefix, encoding.encode(&multihash(name, digest)?)))
+}
+
+/// Decodes a string with a multibase prefix, returning the encoding and the decoded bytes.
+pub(crate) fn multibase_decode(s: &str) -> Option<(Encoding, Vec<u8>)> {
+    let mut chars = s.chars();
+    let encoding = match chars.next()? {
+        'f' | 'F' => Encoding::Hex,
+        'b' | 'B' => Encoding::Base32,
+              'u' => Encoding::Base64Url,
+              'z' => Encoding::Base58,
+                _ => return None,
+    };
+    Some((encoding, encoding.decode(chars.as_str())?))
+}
+
+/// Decodes a multibase encoded multihash, returning the encoding, the name of the algorithm and
+/// the digest, or `None` if `s` is not a multihash of a supported algorithm.
+pub fn decode(s: &str) -> Option<(Encoding, String, Vec<u8>)> {
+    let (encoding, bytes) = multibase_decode(s)?;
+
+    let (code, bytes) = read_varint(&bytes)?;
+    let (size, digest) = read_varint(bytes)?;
+    if digest.len() as u64 != size {
+        return None;
+    }
+    Some((encoding, algorithm(code, digest.len())?, digest.to_vec()))
+}
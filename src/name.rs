@@ -0,0 +1,346 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::encoding::Encoding;
+use crate::magic::{detect_extension, is_extension_of, read_head};
+use crate::shard::Shard;
+use crate::template::Template;
+
+/// Compound filename extensions recognized by [`SuffixPolicy::Known`].
+pub const KNOWN_EXTENSIONS: &[&str] = &[
+    "tar.br", "tar.bz2", "tar.gz", "tar.lz", "tar.lz4", "tar.lzma", "tar.xz", "tar.z", "tar.zst",
+    "d.ts", "min.css", "min.js", "user.css", "user.js",
+];
+
+/// Aliases of filename extensions mapped by [`ExtensionAliases`] by default, each followed by the
+/// usual form of the extension.
+pub const EXTENSION_ALIASES: &[(&str, &str)] = &[
+    ("jpeg", "jpg"), ("jpe", "jpg"), ("tiff", "tif"), ("htm", "html"), ("mpeg", "mpg"),
+    ("markdown", "md"),
+];
+
+/// Which filename extension suffixes of the original file name are kept in the destination name.
+///
+/// A suffix is a part of the file name that follows a dot (.) character, consists only of ASCII
+/// letters and digits, and contains at least one letter. Suffixes are taken from the end of the
+/// file name up to the first part that is not a suffix, so `v1.2 notes.txt` has the single suffix
+/// `txt` and `NIST.1.txt` has the single suffix `txt`. Leading dots belong to the stem of the file
+/// name, so `.bashrc` has no suffixes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SuffixPolicy {
+    /// Keep all suffixes, e.g. `tar.gz` of `backup.tar.gz`.
+    #[default]
+    All,
+    /// Keep only the last suffix, e.g. `gz` of `backup.tar.gz`.
+    Last,
+    /// Keep no suffixes.
+    None,
+    /// Keep at most the given number of suffixes from the end of the file name.
+    AtMost(usize),
+    /// Keep the longest of the [`KNOWN_EXTENSIONS`] the file name ends with, or else the last
+    /// suffix.
+    Known,
+}
+
+/// Error returned when parsing a [`SuffixPolicy`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseSuffixPolicyError(String);
+
+impl fmt::Display for ParseSuffixPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid suffix policy {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseSuffixPolicyError {}
+
+impl FromStr for SuffixPolicy {
+    type Err = ParseSuffixPolicyError;
+
+    fn from_str(s: &str) -> Result<SuffixPolicy, ParseSuffixPolicyError> {
+        match s {
+              "all" => Ok(SuffixPolicy::All),
+             "last" => Ok(SuffixPolicy::Last),
+             "none" => Ok(SuffixPolicy::None),
+            "known" => Ok(SuffixPolicy::Known),
+                  _ => s.parse().map(SuffixPolicy::AtMost)
+                        .map_err(|_| ParseSuffixPolicyError(s.to_string())),
+        }
+    }
+}
+
+/// Whether the filename extension of the original file name is checked against the type of the
+/// file detected from its content with [`detect_extension`](crate::detect_extension).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ExtPolicy {
+    /// Keep the suffixes of the original file name as they are.
+    #[default]
+    Keep,
+    /// Add the detected extension to a file name without suffixes.
+    Detect,
+    /// Add the detected extension to a file name without suffixes, and replace the last suffix
+    /// with it if the suffix is not an extension of the detected type.
+    Fix,
+}
+
+/// Error returned when parsing an [`ExtPolicy`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseExtPolicyError(String);
+
+impl fmt::Display for ParseExtPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid extension policy {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseExtPolicyError {}
+
+impl FromStr for ExtPolicy {
+    type Err = ParseExtPolicyError;
+
+    fn from_str(s: &str) -> Result<ExtPolicy, ParseExtPolicyError> {
+        match s {
+              "keep" => Ok(ExtPolicy::Keep),
+            "detect" => Ok(ExtPolicy::Detect),
+               "fix" => Ok(ExtPolicy::Fix),
+                   _ => Err(ParseExtPolicyError(s.to_string())),
+        }
+    }
+}
+
+/// Lowercases filename extension suffixes and maps their aliases to the usual form, e.g. both
+/// `JPEG` and `jpeg` to `jpg`, so that files with identical content get the same name.
+///
+/// The [`Default`] table has the [`EXTENSION_ALIASES`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtensionAliases {
+    aliases: BTreeMap<String, String>,
+}
+
+/// Error returned when reading the aliases of [`ExtensionAliases::read_config`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseExtensionAliasesError {
+    line: usize,
+    text: String,
+}
+
+impl fmt::Display for ParseExtensionAliasesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid extension aliases on line {}: {}", self.line, self.text)
+    }
+}
+
+impl std::error::Error for ParseExtensionAliasesError {}
+
+impl Default for ExtensionAliases {
+    fn default() -> ExtensionAliases {
+        let mut aliases = ExtensionAliases { aliases: BTreeMap::new() };
+        for (alias, extension) in EXTENSION_ALIASES {
+            aliases.insert(alias, extension);
+        }
+        aliases
+    }
+}
+
+impl ExtensionAliases {
+    /// Maps the suffix `alias` to `extension`, ignoring case.
+    pub fn insert(&mut self, alias: &str, extension: &str) {
+        self.aliases.insert(alias.to_ascii_lowercase(), extension.to_ascii_lowercase());
+    }
+
+    /// Adds the aliases of `config`, in which each line lists an extension followed by its
+    /// aliases, separated by whitespace, e.g. `jpg jpeg jpe`. Empty lines and everything after a
+    /// `#` character are ignored.
+    pub fn read_config(&mut self, config: &str) -> Result<(), ParseExtensionAliasesError> {
+        for (i, line) in config.lines().enumerate() {
+            let words: Vec<&str> = line.split('#').next().unwrap_or_default()
+                .split_whitespace().collect();
+            match words.split_first() {
+                None => (),
+                Some((extension, aliases)) if !aliases.is_empty()
+                    && words.iter().all(|word| is_suffix(word)) =>
+                {
+                    aliases.iter().for_each(|alias| self.insert(alias, extension));
+                },
+                Some(_) => return Err(ParseExtensionAliasesError {
+                    line: i + 1,
+                    text: line.to_string(),
+                }),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `suffix` in lowercase, or the extension it is an alias of.
+    pub fn normalize(&self, suffix: &str) -> String {
+        let suffix = suffix.to_ascii_lowercase();
+        self.aliases.get(&suffix).cloned().unwrap_or(suffix)
+    }
+}
+
+/// Options controlling how destination names are formed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Naming {
+    /// Which filename extension suffixes of the original file name are kept.
+    pub suffixes: SuffixPolicy,
+    /// The aliases the kept suffixes are normalized with, or `None` to keep them verbatim.
+    pub normalize_ext: Option<ExtensionAliases>,
+    /// Whether the extension detected from the content of the file is added to the name or
+    /// replaces a wrong one.
+    pub ext: ExtPolicy,
+    /// How the digest is encoded in the destination name.
+    pub encoding: Encoding,
+    /// Whether the digest is encoded as a multihash with a multibase prefix, so that the name
+    /// identifies the algorithm and the encoding.
+    pub multihash: bool,
+    /// The number of characters of the encoded digest to keep in the destination name, or `None`
+    /// to keep all of them. A [`Plan`](crate::Plan) keeps more characters where needed to tell
+    /// apart files with different content.
+    pub length: Option<usize>,
+    /// The template the destination name is formed from instead of the digest followed by the
+    /// suffixes.
+    pub format: Option<Template>,
+    /// The subdirectories the destination is placed in, formed from the leading characters of the
+    /// encoded digest.
+    pub shard: Shard,
+    /// The directory the destination is placed in instead of the directory of the original file.
+    pub target: Option<PathBuf>,
+}
+
+fn is_suffix(s: &str) -> bool {
+    s.chars().all(|c| c.is_ascii_alphanumeric()) && s.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+/// Returns the suffixes of the file name of `path`, in order, as selected by `policy`.
+pub fn suffixes(path: &Path, policy: SuffixPolicy) -> Vec<String> {
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => return Vec::new(),
+    };
+
+    // Leading dots belong to the stem, the first remaining part is the stem itself
+    let parts: Vec<&str> = name.trim_start_matches('.').split('.').skip(1).collect();
+    let first = parts.iter().rposition(|part| !is_suffix(part)).map_or(0, |i| i + 1);
+    let all = &parts[first..];
+
+    let keep = match policy {
+        SuffixPolicy::All => all.len(),
+        SuffixPolicy::Last => all.len().min(1),
+        SuffixPolicy::None => 0,
+        SuffixPolicy::AtMost(n) => all.len().min(n),
+        SuffixPolicy::Known => (2..=all.len()).rev()
+            .find(|&n| KNOWN_EXTENSIONS.iter()
+                .any(|ext| ext.eq_ignore_ascii_case(&all[all.len() - n..].join("."))))
+            .unwrap_or(all.len().min(1)),
+    };
+
+    all[all.len() - keep..].iter().map(|s| s.to_string()).collect()
+}
+
+/// Returns the suffixes of the file name of `path` that are kept in the destination name according
+/// to `naming`, normalized if `naming` has [`ExtensionAliases`].
+fn kept_suffixes(path: &Path, naming: &Naming) -> Vec<String> {
+    let suffixes = suffixes(path, naming.suffixes);
+    match &naming.normalize_ext {
+        Some(aliases) => suffixes.iter().map(|suffix| aliases.normalize(suffix)).collect(),
+        None => suffixes,
+    }
+}
+
+/// Returns `path` with the file name changed according to `policy` so that it has the extension
+/// `detected` from the content of the file.
+pub(crate) fn with_detected_ext(path: &Path, policy: ExtPolicy, detected: Option<&str>) -> PathBuf {
+    let (extension, name) = match (detected, path.file_name()) {
+        (Some(extension), Some(name)) if policy != ExtPolicy::Keep => (extension, name),
+        _ => return path.to_path_buf(),
+    };
+    match suffixes(path, SuffixPolicy::Last).first() {
+        None => {
+            let mut name = name.to_os_string();
+            name.push(".");
+            name.push(extension);
+            path.with_file_name(name)
+        },
+        Some(last) if policy == ExtPolicy::Fix && !is_extension_of(last, extension) => {
+            let name = name.to_string_lossy();
+            path.with_file_name(format!("{}.{}", &name[..name.len() - last.len() - 1], extension))
+        },
+        Some(_) => path.to_path_buf(),
+    }
+}
+
+/// Forms the name of a file with `digest` followed by the suffixes of `path` kept by `naming`.
+pub(crate) fn suffixed_name(digest: &str, path: &Path, naming: &Naming) -> String {
+    let mut name = digest.to_string();
+    for suffix in kept_suffixes(path, naming) {
+        name.push('.');
+        name.push_str(&suffix);
+    }
+    name
+}
+
+/// Forms the destination file name for the file at `path` from its `digest`, which is already
+/// encoded with `naming.encoding`.
+///
+/// The filename extension suffixes of `path` selected by `naming` are appended to the digest.
+/// Unless `naming.ext` is [`ExtPolicy::Keep`], the type of the file is detected from its first
+/// bytes, which are read from the file. Use [`destination_name_with_head`] to pass bytes that are
+/// already read instead, e.g. while hashing the file.
+pub fn destination_name(digest: &str, path: &Path, naming: &Naming) -> String {
+    destination_name_with_head(digest, path, &head(path, naming), naming)
+}
+
+/// Forms the destination file name for the file at `path` like [`destination_name`], but detects
+/// the type of the file from `head`, the first bytes of its content, without reading the file.
+/// At most [`MAGIC_LEN`](crate::MAGIC_LEN) bytes of `head` are looked at.
+pub fn destination_name_with_head(digest: &str, path: &Path, head: &[u8], naming: &Naming)
+    -> String
+{
+    let detected = match naming.ext {
+        ExtPolicy::Keep => None,
+        _ => detect_extension(head),
+    };
+    suffixed_name(digest, &with_detected_ext(path, naming.ext, detected), naming)
+}
+
+/// Reads the first bytes of the file at `path` if `naming` detects its type from them, or else
+/// returns no bytes.
+fn head(path: &Path, naming: &Naming) -> Vec<u8> {
+    match naming.ext {
+        ExtPolicy::Keep => Vec::new(),
+        _ => read_head(path).unwrap_or_default(),
+    }
+}
+
+/// Returns the directory of the destination for the file at `path` with the encoded `digest`,
+/// which is `naming.target` or else the directory of `path`, followed by the subdirectories for
+/// `digest` if `naming.shard` has any. A `path` that already is in its subdirectories keeps them
+/// instead of getting new ones.
+pub(crate) fn destination_dir(digest: &str, path: &Path, naming: &Naming) -> PathBuf {
+    match &naming.target {
+        Some(target) => target.join(naming.shard.dirs(digest)),
+        None => naming.shard.dir(path.parent().unwrap_or(Path::new("")), digest),
+    }
+}
+
+/// Forms the destination path for the file at `path` from its `digest`. The destination is in
+/// the same directory as `path` unless `naming` gives a target directory, and in the
+/// subdirectories for `digest` if `naming.shard` has any.
+pub fn destination_path(digest: &str, path: &Path, naming: &Naming) -> PathBuf {
+    destination_path_with_head(digest, path, &head(path, naming), naming)
+}
+
+/// Forms the destination path for the file at `path` like [`destination_path`], but detects the
+/// type of the file from `head` like [`destination_name_with_head`].
+pub fn destination_path_with_head(digest: &str, path: &Path, head: &[u8], naming: &Naming)
+    -> PathBuf
+{
+    let name = destination_name_with_head(digest, path, head, naming);
+    destination_dir(digest, path, naming).join(name)
+}
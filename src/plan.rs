@@ -0,0 +1,570 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::cid;
+use crate::error::{Error, Result};
+use crate::hash::{Algorithm, CHUNK_SIZE, hash_file_with_progress, read_full};
+use crate::magic::{MAGIC_LEN, detect_extension};
+use crate::multihash;
+use crate::name::{ExtPolicy, Naming, destination_dir, suffixed_name, with_detected_ext};
+use crate::shard::Shard;
+use crate::template::{Fields, Template};
+use crate::verify::{is_hash_name, is_shortened_hash_name};
+use crate::walk::{Walk, expand_paths};
+
+/// What to do with each file of a [`Plan`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Rename the file to its destination path.
+    Rename,
+    /// Copy the file to its destination path, leaving the original in place.
+    Copy,
+    /// Create a hard link to the file at its destination path.
+    HardLink,
+    /// Create a symbolic link to the file at its destination path.
+    Symlink(LinkTarget),
+    /// Leave the file untouched.
+    DryRun,
+}
+
+/// How the target of a symbolic link created by [`Mode::Symlink`] refers to the file.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// The absolute path of the file.
+    #[default]
+    Absolute,
+    /// The path of the file relative to the directory of the link.
+    Relative,
+}
+
+/// What to do when the destination path of a file already exists.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Clobber {
+    /// Skip the file if the destination has identical content, otherwise refuse with
+    /// [`Error::DestinationExists`].
+    #[default]
+    Verify,
+    /// Skip the file without looking at the destination.
+    NoClobber,
+    /// Replace the destination.
+    Force,
+}
+
+/// The result of applying a [`Mode`] to an [`Entry`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The mode was applied to the file.
+    Done,
+    /// Nothing was done because the file already is at its destination path.
+    Unchanged,
+    /// The file was skipped because the destination exists with identical content.
+    Identical,
+    /// The file was skipped because the destination exists and [`Clobber::NoClobber`] was given.
+    Exists,
+}
+
+/// A single file and the destination path derived from its content.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    /// The digest of the content of the file, encoded as in the destination name.
+    pub digest: String,
+    /// The number of characters of `digest` in the destination name.
+    pub length: usize,
+}
+
+impl Entry {
+    /// Applies `mode` to the file of this entry, handling an existing destination according to
+    /// `clobber`. Missing directories of the destination path are created.
+    ///
+    /// Unless `clobber` is [`Clobber::Force`], a destination that appears while the mode is
+    /// applied is not replaced, but handled like one that existed before.
+    pub fn apply(&self, mode: Mode, clobber: Clobber) -> Result<Outcome> {
+        if mode == Mode::DryRun {
+            return Ok(Outcome::Done);
+        }
+
+        let io_error = |source: io::Error| match mode {
+            Mode::Copy => Error::Copy { path: self.source.clone(), source },
+            Mode::HardLink if source.kind() == io::ErrorKind::CrossesDevices => Error::CrossDevice {
+                path: self.source.clone(),
+                destination: self.destination.clone(),
+            },
+            Mode::HardLink | Mode::Symlink(_) => Error::Link { path: self.source.clone(), source },
+            _ => Error::Rename { path: self.source.clone(), source },
+        };
+
+        if self.destination.symlink_metadata().is_ok() {
+            if is_same_file(&self.source, &self.destination).map_err(io_error)? {
+                return Ok(Outcome::Unchanged);
+            }
+            match clobber {
+                Clobber::Force => (),
+                Clobber::NoClobber => return Ok(Outcome::Exists),
+                Clobber::Verify => {
+                    return if is_same_content(&self.source, &self.destination).map_err(io_error)? {
+                        Ok(Outcome::Identical)
+                    } else {
+                        Err(Error::DestinationExists {
+                            path: self.source.clone(),
+                            destination: self.destination.clone(),
+                        })
+                    };
+                },
+            }
+        }
+
+        if let Some(dir) = self.destination.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+
+        let replace = clobber == Clobber::Force;
+        let result = match mode {
+            Mode::Copy => copy_atomic(&self.source, &self.destination, replace),
+            Mode::HardLink => link_atomic(&self.destination, replace,
+                |link| fs::hard_link(&self.source, link)),
+            Mode::Symlink(target) => symlink_target(&self.source, &self.destination, target)
+                .and_then(|target| link_atomic(&self.destination, replace,
+                    |link| symlink(&target, link))),
+            _ => rename(&self.source, &self.destination, replace),
+        };
+        match result {
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && !replace => {
+                self.apply(mode, clobber)
+            },
+            result => result.map(|_| Outcome::Done).map_err(io_error),
+        }
+    }
+}
+
+/// Returns a temporary path in the directory of `destination` that is not a valid hash name.
+fn temporary_path(destination: &Path) -> PathBuf {
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(destination.file_name().unwrap_or_default());
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    destination.with_file_name(tmp_name)
+}
+
+/// Renames the file at `source` to `destination`, failing with [`io::ErrorKind::AlreadyExists`]
+/// instead of replacing an existing `destination`.
+///
+/// The file is hard linked to `destination`, which fails if `destination` exists, and then removed
+/// from `source`. On filesystems without hard links, `destination` is checked right before it is
+/// renamed to instead.
+fn rename_noreplace(source: &Path, destination: &Path) -> io::Result<()> {
+    match fs::hard_link(source, destination) {
+        Ok(()) => fs::remove_file(source),
+        Err(err) if matches!(err.kind(), ErrorKind::Unsupported | ErrorKind::PermissionDenied) => {
+            match destination.symlink_metadata() {
+                Ok(_) => Err(io::Error::from(ErrorKind::AlreadyExists)),
+                Err(_) => fs::rename(source, destination),
+            }
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// Copies the file at `source` to `destination` through a temporary file in the destination
+/// directory, so that a partially written copy never appears at `destination`. An existing
+/// `destination` is replaced only if `replace` is true.
+fn copy_atomic(source: &Path, destination: &Path, replace: bool) -> io::Result<()> {
+    let tmp = temporary_path(destination);
+
+    let result = fs::copy(source, &tmp)
+        .and_then(|_| fs::File::open(&tmp)?.sync_all())
+        .and_then(|_| match replace {
+            true => fs::rename(&tmp, destination),
+            false => rename_noreplace(&tmp, destination),
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Renames the file at `source` to `destination`, or copies it and removes the original if
+/// `destination` is on another filesystem. An existing `destination` is replaced only if `replace`
+/// is true.
+fn rename(source: &Path, destination: &Path, replace: bool) -> io::Result<()> {
+    let result = match replace {
+        true => fs::rename(source, destination),
+        false => rename_noreplace(source, destination),
+    };
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            copy_atomic(source, destination, replace)?;
+            fs::remove_file(source)
+        },
+        result => result,
+    }
+}
+
+/// Creates a link at `destination` by calling `link`. If `replace` is true, the link is created at
+/// a temporary path and renamed to `destination`, which replaces an existing `destination`.
+fn link_atomic<F>(destination: &Path, replace: bool, link: F) -> io::Result<()>
+where
+    F: FnOnce(&Path) -> io::Result<()>,
+{
+    if !replace {
+        return link(destination);
+    }
+    let tmp = temporary_path(destination);
+
+    link(&tmp)?;
+    let result = fs::rename(&tmp, destination);
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Returns the target of a symbolic link at `destination` that refers to the file at `source`.
+fn symlink_target(source: &Path, destination: &Path, target: LinkTarget) -> io::Result<PathBuf> {
+    let source = fs::canonicalize(source)?;
+    if target == LinkTarget::Absolute {
+        return Ok(source);
+    }
+
+    let parent = match destination.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = fs::canonicalize(parent)?;
+
+    let common = source.components().zip(parent.components()).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in parent.components().skip(common) {
+        relative.push("..");
+    }
+    relative.extend(source.components().skip(common));
+    Ok(relative)
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+/// Returns the number of leading bytes that `a` and `b` have in common.
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.bytes().zip(b.bytes()).take_while(|(a, b)| a == b).count()
+}
+
+/// Returns the names of the files in `dir` up to the first dot (.) character together with their
+/// paths, sorted by name. An empty `dir` is the current directory.
+fn stems(dir: &Path) -> Vec<(String, PathBuf)> {
+    let dir = match dir.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => dir,
+    };
+    let mut stems: Vec<(String, PathBuf)> = fs::read_dir(dir).into_iter().flatten().flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            Some((name.split('.').next()?.to_string(), entry.path()))
+        })
+        .collect();
+    stems.sort();
+    stems
+}
+
+/// Truncates the digests in the destination names of `entries` to `length` characters, keeping
+/// more characters where needed so that the name of an entry is not a prefix of the name of
+/// another entry with a different digest, or of an existing file with different content, in the
+/// same directory. If `ignore_case` is true, names that differ only in case are considered the
+/// same, as they are on case-insensitive filesystems.
+///
+/// The destination path of the `i`th entry with a truncated digest is formed by `destination`.
+fn shorten<F>(entries: &mut [Entry], length: usize, ignore_case: bool, destination: F)
+where
+    F: Fn(usize, &Entry, &str) -> PathBuf,
+{
+    let dir = |entry: &Entry| entry.destination.parent().map(Path::to_path_buf).unwrap_or_default();
+    let key = |name: &str| match ignore_case {
+        true => name.to_ascii_lowercase(),
+        false => name.to_string(),
+    };
+
+    // Digests are compared by their keys, but only copies of the same digest are left out
+    let mut digests: HashMap<PathBuf, Vec<(String, &str)>> = HashMap::new();
+    for entry in entries.iter() {
+        digests.entry(dir(entry)).or_default().push((key(&entry.digest), &entry.digest));
+    }
+    for digests in digests.values_mut() {
+        digests.sort();
+        digests.dedup();
+    }
+    let mut existing: HashMap<PathBuf, Vec<(String, PathBuf)>> = HashMap::new();
+
+    let lengths: Vec<usize> = entries.iter().map(|entry| {
+        let dir = dir(entry);
+        let digest = key(&entry.digest);
+
+        // The longest prefix shared with another digest is shared with a neighbour in sort order
+        let digests = &digests[&dir];
+        let i = digests.binary_search(&(digest.clone(), entry.digest.as_str())).unwrap_or_default();
+        let batch = [i.checked_sub(1), Some(i + 1)].into_iter().flatten()
+            .filter_map(|j| digests.get(j))
+            .map(|(other, _)| common_prefix_len(&digest, other));
+
+        // Existing files whose names start with the shortest possible name are adjacent
+        let stems = existing.entry(dir.clone()).or_insert_with(|| {
+            let mut stems: Vec<(String, PathBuf)> = stems(&dir).into_iter()
+                .map(|(stem, path)| (key(&stem), path))
+                .collect();
+            stems.sort();
+            stems
+        });
+        let prefix = &digest[..length.min(digest.len())];
+        let first = stems.partition_point(|(stem, _)| stem.as_str() < prefix);
+        let files = stems[first..].iter()
+            .take_while(|(stem, _)| stem.starts_with(prefix))
+            .filter(|(_, path)| !is_same_content(path, &entry.source).unwrap_or(false))
+            .map(|(stem, _)| common_prefix_len(&digest, stem));
+
+        batch.chain(files).map(|n| n + 1).fold(length, usize::max).min(entry.digest.len())
+    }).collect();
+
+    for (i, (entry, length)) in entries.iter_mut().zip(lengths).enumerate() {
+        entry.destination = destination(i, entry, &entry.digest[..length]);
+        entry.length = length;
+    }
+}
+
+/// Returns the size and the modification time of a file from its `metadata`.
+fn stat(metadata: &fs::Metadata) -> (u64, SystemTime) {
+    (metadata.len(), metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH))
+}
+
+/// Forms the destination path for the file at `source` from its encoded `digest`, calculated with
+/// the algorithm named `algorithm`, its size and modification time if the format of `naming`
+/// needs them, and the extension `detected` from its content.
+fn destination(naming: &Naming, algorithm: &str, digest: &str, source: &Path,
+    stat: Option<(u64, SystemTime)>, detected: Option<&str>) -> PathBuf
+{
+    let dir = destination_dir(digest, source, naming);
+    let named = with_detected_ext(source, naming.ext, detected);
+    let template = match &naming.format {
+        Some(template) => template,
+        None => return dir.join(suffixed_name(digest, &named, naming)),
+    };
+    let (size, modified) = stat.unwrap_or((0, SystemTime::UNIX_EPOCH));
+    let fields = Fields {
+        digest,
+        algorithm,
+        path: &named,
+        suffixes: naming.suffixes,
+        normalize_ext: naming.normalize_ext.as_ref(),
+        size,
+        modified,
+    };
+    dir.join(template.render(&fields))
+}
+
+/// Returns whether `a` and `b` refer to the same file.
+fn is_same_file(a: &Path, b: &Path) -> io::Result<bool> {
+    Ok(fs::canonicalize(a)? == fs::canonicalize(b)?)
+}
+
+/// Returns whether the files at `a` and `b` have identical content.
+pub(crate) fn is_same_content(a: &Path, b: &Path) -> io::Result<bool> {
+    let (mut a, mut b) = (fs::File::open(a)?, fs::File::open(b)?);
+    if a.metadata()?.len() != b.metadata()?.len() {
+        return Ok(false);
+    }
+
+    let mut buffer_a = vec![0; CHUNK_SIZE];
+    let mut buffer_b = vec![0; CHUNK_SIZE];
+    loop {
+        let n = read_full(&mut a, &mut buffer_a)?;
+        if n != read_full(&mut b, &mut buffer_b)? || buffer_a[..n] != buffer_b[..n] {
+            return Ok(false);
+        }
+        if n == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// The destination paths of a set of files.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Plan {
+    entries: Vec<Entry>,
+}
+
+impl Plan {
+    /// Hashes each of `paths` with `alg` and derives their destination paths according to
+    /// `naming`.
+    ///
+    /// All paths are checked to exist before any of them is hashed.
+    pub fn new<I, P>(alg: &mut Algorithm, naming: &Naming, paths: I) -> Result<Plan>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        Plan::with_progress(alg, naming, paths, |_, _| {})
+    }
+
+    /// Like [`Plan::new`], but calls `progress` with the path of the file being hashed and each
+    /// chunk read from it.
+    pub fn with_progress<I, P, F>(alg: &mut Algorithm, naming: &Naming, paths: I, progress: F)
+        -> Result<Plan>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+        F: FnMut(&Path, &[u8]),
+    {
+        let (plan, mut errors) = Plan::build(alg, naming, paths, false, progress);
+        match errors.is_empty() {
+            true => Ok(plan),
+            false => Err(errors.remove(0)),
+        }
+    }
+
+    /// Like [`Plan::with_progress`], but instead of stopping at the first file that does not
+    /// exist or cannot be hashed, leaves such files out of the plan and returns their errors.
+    pub fn keep_going<I, P, F>(alg: &mut Algorithm, naming: &Naming, paths: I, progress: F)
+        -> (Plan, Vec<Error>)
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+        F: FnMut(&Path, &[u8]),
+    {
+        Plan::build(alg, naming, paths, true, progress)
+    }
+
+    fn build<I, P, F>(alg: &mut Algorithm, naming: &Naming, paths: I, keep_going: bool, mut progress: F)
+        -> (Plan, Vec<Error>)
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+        F: FnMut(&Path, &[u8]),
+    {
+        let mut paths: Vec<PathBuf> = paths.into_iter().map(|p| p.as_ref().to_path_buf()).collect();
+        let mut errors = Vec::new();
+
+        paths.retain(|p| p.exists() || { errors.push(Error::FileNotFound(p.clone())); false });
+        if !keep_going && !errors.is_empty() {
+            errors.truncate(1);
+            return (Plan::default(), errors);
+        }
+
+        // CIDs are self-describing already, so they are never wrapped in a multihash
+        let is_cid = alg.name() == "cid";
+        if (naming.multihash || is_cid) && naming.encoding.multibase().is_none() {
+            return (Plan::default(), vec![Error::NoMultibase(naming.encoding)]);
+        }
+        let code = multihash::code(alg.name(), alg.output_size());
+        if naming.multihash && !is_cid && code.is_none() {
+            return (Plan::default(), vec![Error::NoMultihashCode(alg.name().to_string())]);
+        }
+
+        let name = alg.name().to_string();
+        let encode = |digest: &[u8]| if is_cid {
+            cid::encode(digest, naming.encoding).unwrap_or_default()
+        } else if naming.multihash {
+            multihash::encode(&name, digest, naming.encoding).unwrap_or_default()
+        } else {
+            naming.encoding.encode(digest)
+        };
+
+        let needs_metadata = naming.format.as_ref().is_some_and(Template::needs_metadata);
+        let mut entries = Vec::with_capacity(paths.len());
+        let mut details = Vec::with_capacity(paths.len());
+        for source in paths {
+            let stat = match needs_metadata {
+                true => fs::metadata(&source).map(|metadata| Some(stat(&metadata))),
+                false => Ok(None),
+            };
+            // The type of the file is detected from the first chunks read for hashing
+            let mut head = Vec::new();
+            let hashed = stat.and_then(|stat| {
+                hash_file_with_progress(alg, &source, |chunk| {
+                    if naming.ext != ExtPolicy::Keep && head.len() < MAGIC_LEN {
+                        head.extend_from_slice(&chunk[..chunk.len().min(MAGIC_LEN - head.len())]);
+                    }
+                    progress(&source, chunk)
+                }).map(|digest| (encode(&digest), stat))
+            });
+            let (digest, stat) = match hashed {
+                Ok(hashed) => hashed,
+                Err(err) => {
+                    errors.push(Error::Digest { path: source, source: err });
+                    if keep_going {
+                        continue;
+                    }
+                    return (Plan::default(), errors);
+                },
+            };
+            let detected = detect_extension(&head);
+            let destination = destination(naming, &name, &digest, &source, stat, detected);
+            let length = digest.len();
+            entries.push(Entry { source, destination, digest, length });
+            details.push((stat, detected));
+        }
+
+        // Names are never shorter than the subdirectories they are placed in
+        if let Some(length) = naming.length {
+            let length = length.max(naming.shard.width());
+            let ignore_case = naming.encoding.is_mixed_case();
+            shorten(&mut entries, length, ignore_case, |i, entry, digest| {
+                let (stat, detected) = details[i];
+                destination(naming, &name, digest, &entry.source, stat, detected)
+            });
+        }
+
+        (Plan { entries }, errors)
+    }
+
+    /// Plans moving the hash-named files below the directory `root` to the subdirectories of the
+    /// `shard` layout for their names, e.g. from `ab/ab12cd.png` to `ab/12/ab12cd.png`.
+    ///
+    /// The files are not hashed, so their names are trusted. Names truncated with
+    /// [`Naming::length`] are moved only if they are in subdirectories formed from their leading
+    /// characters. Files whose names do not have the form of a digest and files that already are
+    /// in place are left out of the plan.
+    pub fn reshard(root: &Path, shard: &Shard) -> Result<Plan> {
+        if !root.exists() {
+            return Err(Error::FileNotFound(root.to_path_buf()));
+        }
+        if !root.is_dir() {
+            let source = io::Error::from(io::ErrorKind::NotADirectory);
+            return Err(Error::Walk { path: root.to_path_buf(), source });
+        }
+
+        let walk = Walk { recursive: true, ..Walk::default() };
+        let entries = expand_paths([root], &walk)?.into_iter()
+            .filter(|source| is_hash_name(source) || is_shortened_hash_name(source, root))
+            .filter_map(|source| {
+                let name = source.file_name()?.to_str()?.to_string();
+                let destination = shard.path(root, &name);
+                let digest = name.split('.').next()?.to_string();
+                let length = digest.len();
+                (destination != source).then_some(Entry { source, destination, digest, length })
+            })
+            .collect();
+        Ok(Plan { entries })
+    }
+
+    /// Returns the entries of the plan in the order the files were given.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Applies `mode` to every entry of the plan, stopping at the first error.
+    pub fn execute(&self, mode: Mode, clobber: Clobber) -> Result<()> {
+        self.entries.iter().try_for_each(|entry| entry.apply(mode, clobber).map(|_| ()))
+    }
+}
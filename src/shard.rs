@@ -0,0 +1,128 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// How hash-named files are spread over subdirectories named by the leading characters of their
+/// names, like `.git/objects`.
+///
+/// Each level of subdirectories takes the given number of characters, so with the levels 2 and 2
+/// the file `ab12cd.png` is placed at `ab/12/ab12cd.png`. The default has no levels, i.e., the
+/// files are placed directly in the directory.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Shard {
+    levels: Vec<usize>,
+}
+
+/// Error returned when parsing a [`Shard`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseShardError(String);
+
+impl fmt::Display for ParseShardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid shard layout {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseShardError {}
+
+impl FromStr for Shard {
+    type Err = ParseShardError;
+
+    /// Parses the number of characters of each level separated by slashes (/), e.g. `2/2`, or
+    /// `0` for no levels.
+    fn from_str(s: &str) -> Result<Shard, ParseShardError> {
+        if s == "0" {
+            return Ok(Shard::default());
+        }
+        s.split('/')
+            .map(|level| level.parse::<usize>().ok().filter(|&n| n > 0))
+            .collect::<Option<Vec<usize>>>()
+            .map(|levels| Shard { levels })
+            .ok_or_else(|| ParseShardError(s.to_string()))
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.levels.is_empty() {
+            true => f.write_str("0"),
+            false => {
+                let levels: Vec<String> = self.levels.iter().map(|n| n.to_string()).collect();
+                f.write_str(&levels.join("/"))
+            },
+        }
+    }
+}
+
+impl Shard {
+    /// Returns a layout with the given number of characters on each level.
+    pub fn new(levels: &[usize]) -> Shard {
+        Shard { levels: levels.iter().copied().filter(|&n| n > 0).collect() }
+    }
+
+    /// Returns the number of characters of a name that all levels take together.
+    pub fn width(&self) -> usize {
+        self.levels.iter().sum()
+    }
+
+    /// Returns the subdirectories for a file named by `digest`, e.g. `ab/12` for `ab12cd`. A
+    /// digest that is shorter than [`Shard::width`] gets no subdirectories.
+    pub fn dirs(&self, digest: &str) -> PathBuf {
+        let mut dirs = PathBuf::new();
+        if digest.chars().count() < self.width() {
+            return dirs;
+        }
+        let mut chars = digest.chars();
+        for &n in &self.levels {
+            dirs.push(chars.by_ref().take(n).collect::<String>());
+        }
+        dirs
+    }
+
+    /// Returns the directory that contains the subdirectories of `dir` for `digest`, i.e., `dir`
+    /// without the subdirectories for `digest` if `dir` already is one of them, or else `dir`
+    /// itself.
+    pub fn root<'a>(&self, dir: &'a Path, digest: &str) -> &'a Path {
+        let dirs = self.dirs(digest);
+        match dir.ends_with(&dirs) {
+            true => dir.ancestors().nth(dirs.components().count()).unwrap_or(dir),
+            false => dir,
+        }
+    }
+
+    /// Returns the directory in which a file named by `digest` that is now in `dir` is placed,
+    /// i.e., the subdirectories for `digest` below the [`Shard::root`] of `dir`.
+    pub fn dir(&self, dir: &Path, digest: &str) -> PathBuf {
+        self.root(dir, digest).join(self.dirs(digest))
+    }
+
+    /// Returns the path of the file named `name` in the directory `root` laid out with this
+    /// layout. The subdirectories are formed from the part of `name` before the first dot (.).
+    pub fn path(&self, root: &Path, name: &str) -> PathBuf {
+        let digest = name.split('.').next().unwrap_or_default();
+        root.join(self.dirs(digest)).join(name)
+    }
+
+    /// Returns whether the file at `path` is in the subdirectories for its name.
+    pub fn is_placed(&self, path: &Path) -> bool {
+        let digest = path.file_name().and_then(|name| name.to_str())
+            .and_then(|name| name.split('.').next())
+            .unwrap_or_default();
+        path.parent().unwrap_or(Path::new("")).ends_with(self.dirs(digest))
+    }
+}
+
+/// Removes the directories between the file at `path` and `root` that are empty, starting from
+/// the directory of `path`, e.g. after the file has been moved to another layout.
+pub fn prune(path: &Path, root: &Path) {
+    for dir in path.ancestors().skip(1).take_while(|dir| *dir != root && dir.starts_with(root)) {
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,210 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use chrono::format::{Item, StrftimeItems};
+
+use crate::name::{ExtensionAliases, SuffixPolicy, suffixes};
+
+/// The format of `{mtime}` without an explicit format.
+const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d";
+
+/// A part of a [`Template`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Hash(Option<usize>),
+    Algo,
+    Stem,
+    Ext,
+    Exts,
+    Size,
+    Mtime(String),
+}
+
+/// A template for destination names, e.g. `{mtime:%Y/%m}/{hash}.{exts}`.
+///
+/// The placeholders are replaced as follows:
+///
+///  - `{hash}` with the encoded digest, and `{hash:N}` with its first N characters,
+///  - `{algo}` with the name of the digest algorithm,
+///  - `{stem}` with the original file name without the suffixes of `{exts}`,
+///  - `{ext}` with the last of the suffixes of `{exts}`,
+///  - `{exts}` with the suffixes selected by the [`SuffixPolicy`], separated by dots and
+///    normalized with the [`ExtensionAliases`] if there are any,
+///  - `{size}` with the size of the file in bytes, and
+///  - `{mtime}` with the local date the file was last modified, or `{mtime:FORMAT}` with the
+///    date and time in a `strftime` format.
+///
+/// `{{` and `}}` stand for literal braces. A dot (.) before an `{ext}` or `{exts}` that is empty
+/// is left out, so `{hash}.{exts}` gives just the digest for a file without suffixes. A slash (/)
+/// separates directories, which are relative to the directory of the original file or the target
+/// directory.
+///
+/// Every template includes the full `{hash}`, so that files with different content get
+/// different names. `{hash:N}` may be used in addition to it, e.g. in `{hash:2}/{hash}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+/// The values of the placeholders of a [`Template`] for a file.
+pub struct Fields<'a> {
+    /// The encoded digest of the content of the file.
+    pub digest: &'a str,
+    /// The name of the digest algorithm.
+    pub algorithm: &'a str,
+    /// The original path of the file, with the extension detected from its content if
+    /// [`Naming::ext`](crate::Naming::ext) asks for it.
+    pub path: &'a Path,
+    /// Which filename extension suffixes of the original file name are kept.
+    pub suffixes: SuffixPolicy,
+    /// The aliases the kept suffixes are normalized with, or `None` to keep them verbatim.
+    pub normalize_ext: Option<&'a ExtensionAliases>,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The time the file was last modified.
+    pub modified: SystemTime,
+}
+
+/// Error returned when parsing a [`Template`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseTemplateError {
+    template: String,
+    reason: String,
+}
+
+impl fmt::Display for ParseTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid format {:?}: {}", self.template, self.reason)
+    }
+}
+
+impl std::error::Error for ParseTemplateError {}
+
+/// Parses the placeholder `s`, i.e., the part of the template between braces.
+fn placeholder(s: &str) -> Result<Part, String> {
+    let (name, arg) = match s.split_once(':') {
+        Some((name, arg)) => (name, Some(arg)),
+        None => (s, None),
+    };
+    match (name, arg) {
+         ("hash", None) => Ok(Part::Hash(None)),
+        ("hash", Some(n)) => match n.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(Part::Hash(Some(n))),
+            _ => Err(format!("invalid hash length {}", n)),
+        },
+         ("algo", None) => Ok(Part::Algo),
+         ("stem", None) => Ok(Part::Stem),
+          ("ext", None) => Ok(Part::Ext),
+         ("exts", None) => Ok(Part::Exts),
+         ("size", None) => Ok(Part::Size),
+        ("mtime", None) => Ok(Part::Mtime(DEFAULT_TIME_FORMAT.to_string())),
+        ("mtime", Some(format)) => match StrftimeItems::new(format).any(|item| item == Item::Error) {
+            true => Err(format!("invalid time format {}", format)),
+            false => Ok(Part::Mtime(format.to_string())),
+        },
+                      _ => Err(format!("unknown placeholder {{{}}}", s)),
+    }
+}
+
+impl FromStr for Template {
+    type Err = ParseTemplateError;
+
+    fn from_str(s: &str) -> Result<Template, ParseTemplateError> {
+        let error = |reason: String| ParseTemplateError { template: s.to_string(), reason };
+
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => { chars.next(); literal.push('{'); },
+                '}' if chars.as_str().starts_with('}') => { chars.next(); literal.push('}'); },
+                '{' => {
+                    let (inner, rest) = chars.as_str().split_once('}')
+                        .ok_or_else(|| error("unmatched {".to_string()))?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(placeholder(inner).map_err(error)?);
+                    chars = rest.chars();
+                },
+                '}' => return Err(error("unmatched }".to_string())),
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        if !parts.contains(&Part::Hash(None)) {
+            return Err(error("must include the full {hash}".to_string()));
+        }
+        if s.split('/').any(|component| matches!(component, "" | "." | "..")) {
+            return Err(error("must be a relative path without empty, . or .. components".to_string()));
+        }
+
+        Ok(Template { source: s.to_string(), parts })
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Template {
+    /// Returns whether the template refers to the size or modification time of the file.
+    pub fn needs_metadata(&self) -> bool {
+        self.parts.iter().any(|part| matches!(part, Part::Size | Part::Mtime(_)))
+    }
+
+    /// Forms the destination path relative to the directory of the original file or the target
+    /// directory from `fields`.
+    ///
+    /// Components of the result that would leave that directory are left out.
+    pub fn render(&self, fields: &Fields) -> PathBuf {
+        let exts = suffixes(fields.path, fields.suffixes);
+        let name = fields.path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let stem = match exts.is_empty() {
+            true => &name[..],
+            false => &name[..name.len() - exts.join(".").len() - 1],
+        };
+        let exts: Vec<String> = match fields.normalize_ext {
+            Some(aliases) => exts.iter().map(|ext| aliases.normalize(ext)).collect(),
+            None => exts,
+        };
+
+        let mut rendered = String::new();
+        for part in &self.parts {
+            let value = match part {
+                Part::Literal(literal) => literal.clone(),
+                Part::Hash(None) => fields.digest.to_string(),
+                Part::Hash(Some(n)) => fields.digest.chars().take(*n).collect(),
+                Part::Algo => fields.algorithm.to_string(),
+                Part::Stem => stem.to_string(),
+                Part::Ext => exts.last().cloned().unwrap_or_default(),
+                Part::Exts => exts.join("."),
+                Part::Size => fields.size.to_string(),
+                Part::Mtime(format) => chrono::DateTime::<chrono::Local>::from(fields.modified)
+                    .format(format).to_string(),
+            };
+            if value.is_empty() && matches!(part, Part::Ext | Part::Exts) && rendered.ends_with('.') {
+                rendered.pop();
+            }
+            rendered.push_str(&value);
+        }
+
+        Path::new(&rendered).components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect()
+    }
+}
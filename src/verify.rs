@@ -0,0 +1,157 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use std::fs;
+use std::path::Path;
+
+use crate::cid;
+use crate::encoding::Encoding;
+use crate::error::{Error, Result};
+use crate::hash::{ALGORITHMS, Algorithm, hash_reader_all};
+use crate::multihash;
+use crate::shard::Shard;
+
+/// The result of verifying that the name of a file matches its content.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// The name of the file is the digest of its content with the named algorithm.
+    Ok(String),
+    /// The name of the file is not the digest of its content with any candidate algorithm.
+    Mismatch,
+    /// The name of the file does not have the form of a digest.
+    NotAHashName,
+    /// The name of the file is the digest of its content with the named algorithm, but the file
+    /// is not in the subdirectories of a [`Shard`] layout for its name.
+    Misplaced(String),
+}
+
+/// A digest that the name of a file claims, with the name of the algorithm if the name
+/// identifies it.
+type Claim = (Option<String>, Vec<u8>);
+
+/// Returns the digests that the name of the file at `path` may claim, i.e., the part of the file
+/// name before the first filename extension suffix, decoded as a CID, as a multihash and with
+/// each of `encodings` that can decode it.
+fn claimed_digests(path: &Path, encodings: &[Encoding]) -> Vec<Claim> {
+    let digest = path.file_name().and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .unwrap_or_default();
+    if digest.is_empty() {
+        return Vec::new();
+    }
+    let cid = cid::decode(digest)
+        .filter(|(encoding, _)| encodings.contains(encoding))
+        .map(|(_, cid)| (Some("cid".to_string()), cid));
+    let multihash = multihash::decode(digest)
+        .filter(|(encoding, _, _)| encodings.contains(encoding))
+        .map(|(_, name, digest)| (Some(name), digest));
+    let plain = encodings.iter()
+        .filter_map(|encoding| encoding.decode(digest))
+        .map(|digest| (None, digest));
+    cid.into_iter().chain(multihash).chain(plain).collect()
+}
+
+/// Returns the algorithms that may have produced the digests of `claimed`, all of them if
+/// `algorithm` is `None` and otherwise only the named one.
+fn candidates(claimed: &[Claim], algorithm: Option<&str>) -> Result<Vec<Algorithm>> {
+    let mut candidates: Vec<Algorithm> = Vec::new();
+    for (claimed, digest) in claimed {
+        let names: Vec<&str> = match (claimed.as_deref(), algorithm) {
+            (Some(name), _) | (None, Some(name)) => vec![name],
+            (None, None) => ALGORITHMS.to_vec(),
+        };
+        for name in names {
+            let alg = Algorithm::new(name)?;
+            let known = candidates.iter().any(|candidate| candidate.name() == alg.name());
+            if alg.output_size() == digest.len() && !known {
+                candidates.push(alg);
+            }
+        }
+    }
+    Ok(candidates)
+}
+
+/// Returns whether the name of the file at `path` has the form of a digest in any encoding,
+/// without reading the file.
+pub(crate) fn is_hash_name(path: &Path) -> bool {
+    let claimed = claimed_digests(path, Encoding::ALL);
+    candidates(&claimed, None).is_ok_and(|candidates| !candidates.is_empty())
+}
+
+/// Returns whether the file at `path` below `root` is named by a digest that may be shortened,
+/// e.g. with [`Naming::length`](crate::Naming::length), without reading the file. The part of the
+/// name before the first dot (.) must consist of the characters of an encoding, and the file must
+/// be in subdirectories of `root` formed from its leading characters like those of a
+/// [`Shard`] layout, so that other files in a store, such as a README, are told apart.
+pub(crate) fn is_shortened_hash_name(path: &Path, root: &Path) -> bool {
+    let Some(stem) = path.file_name().and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .filter(|stem| !stem.is_empty()) else {
+        return false;
+    };
+    let Some(dirs) = path.parent().and_then(|dir| dir.strip_prefix(root).ok())
+        .and_then(|dirs| dirs.to_str()) else {
+        return false;
+    };
+    let dirs: String = dirs.split(std::path::MAIN_SEPARATOR).collect();
+    !dirs.is_empty() && stem.starts_with(&dirs) && Encoding::ALL.iter().any(|encoding| encoding.is_alphabet(stem))
+}
+
+/// Verifies that the name of the file at `path` is the digest of its content.
+///
+/// If the name is a CID or a multihash, the algorithm named in it is used. Otherwise, if
+/// `algorithm` is `None`, the candidate algorithms are inferred from the length of the digest in
+/// the file name, e.g., SHA-256, SHA-512/256, SHA3-256 and others for 64 hexadecimal characters,
+/// and the content is hashed with all of them in a single pass. The verdict names the algorithm
+/// that produced the digest. If `algorithm` is given, only the named algorithm is considered.
+///
+/// Similarly, if `encoding` is `None`, the digest in the file name may be in any of the
+/// [`Encoding::ALL`] encodings, and otherwise only in the given encoding.
+pub fn verify_path(path: &Path, algorithm: Option<&str>, encoding: Option<Encoding>)
+    -> Result<Verdict>
+{
+    if !path.exists() {
+        return Err(Error::FileNotFound(path.to_path_buf()));
+    }
+
+    let encodings = match encoding {
+        Some(encoding) => vec![encoding],
+        None => Encoding::ALL.to_vec(),
+    };
+    let mut claimed = claimed_digests(path, &encodings);
+    if let Some(name) = algorithm {
+        claimed.retain(|(claimed, _)| claimed.as_deref().is_none_or(|claimed| claimed == name));
+    }
+
+    let mut candidates = candidates(&claimed, algorithm)?;
+    if candidates.is_empty() {
+        return Ok(Verdict::NotAHashName);
+    }
+
+    let digest_error = |source| Error::Digest { path: path.to_path_buf(), source };
+    let file = fs::File::open(path).map_err(digest_error)?;
+    let length = file.metadata().map_err(digest_error)?.len();
+    let digests = hash_reader_all(&mut candidates, file, Some(length), |_| {})
+        .map_err(digest_error)?;
+
+    // The algorithm named in a CID or multihash takes precedence over those inferred from length
+    Ok(claimed.iter()
+        .find_map(|(claimed, digest)| candidates.iter().zip(&digests)
+            .find(|(alg, actual)| *actual == digest
+                && claimed.as_deref().is_none_or(|claimed| claimed == alg.name()))
+            .map(|(alg, _)| alg.name().to_string()))
+        .map_or(Verdict::Mismatch, Verdict::Ok))
+}
+
+/// Like [`verify_path`], but also verifies that the file is in the subdirectories of the `shard`
+/// layout for its name, e.g. `ab/12/` for `ab12cd.png`, and returns [`Verdict::Misplaced`] if the
+/// name matches the content but the file is elsewhere.
+pub fn verify_placement(path: &Path, shard: &Shard, algorithm: Option<&str>,
+    encoding: Option<Encoding>) -> Result<Verdict>
+{
+    match verify_path(path, algorithm, encoding)? {
+        Verdict::Ok(alg) if !shard.is_placed(path) => Ok(Verdict::Misplaced(alg)),
+        verdict => Ok(verdict),
+    }
+}
@@ -0,0 +1,91 @@
+/*
+ * Copyright 2023 Thomas Nyman <thomas.nyman@iki.fi>
+ * SPDX-License-Identifier: Apache-2.0 or MIT
+ */
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::error::{Error, Result};
+
+/// Options controlling how directory operands are walked by [`expand_paths`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Walk {
+    /// Walk directory operands instead of passing them through.
+    pub recursive: bool,
+    /// Follow symbolic links to files and directories found in directories.
+    pub follow_symlinks: bool,
+    /// Do not descend into directories on other filesystems than the directory operand.
+    pub one_file_system: bool,
+    /// Do not descend further than the given number of levels below the directory operand,
+    /// e.g., 1 for the files directly in the directory.
+    pub max_depth: Option<usize>,
+}
+
+/// Expands `paths` into the paths of the files to process according to `walk`.
+///
+/// If `walk` is recursive, each directory operand is replaced by the regular files below it, in
+/// order of their file names. Other operands are passed through unchanged.
+pub fn expand_paths<I, P>(paths: I, walk: &Walk) -> Result<Vec<PathBuf>>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let (expanded, mut errors) = expand(paths, walk, false);
+    match errors.is_empty() {
+        true => Ok(expanded),
+        false => Err(errors.remove(0)),
+    }
+}
+
+/// Like [`expand_paths`], but instead of stopping at the first entry of a directory that cannot
+/// be read, skips such entries and returns their errors.
+pub fn expand_paths_keep_going<I, P>(paths: I, walk: &Walk) -> (Vec<PathBuf>, Vec<Error>)
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    expand(paths, walk, true)
+}
+
+fn expand<I, P>(paths: I, walk: &Walk, keep_going: bool) -> (Vec<PathBuf>, Vec<Error>)
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let mut expanded = Vec::new();
+    let mut errors = Vec::new();
+
+    for path in paths {
+        let path = path.as_ref();
+        if !walk.recursive || !path.is_dir() {
+            expanded.push(path.to_path_buf());
+            continue;
+        }
+
+        let mut walker = WalkDir::new(path)
+            .follow_links(walk.follow_symlinks)
+            .same_file_system(walk.one_file_system)
+            .sort_by_file_name();
+        if let Some(depth) = walk.max_depth {
+            walker = walker.max_depth(depth);
+        }
+
+        for entry in walker {
+            match entry {
+                Ok(entry) if entry.file_type().is_file() => expanded.push(entry.into_path()),
+                Ok(_) => (),
+                Err(err) => {
+                    errors.push(Error::Walk {
+                        path: err.path().unwrap_or(path).to_path_buf(),
+                        source: err.into(),
+                    });
+                    if !keep_going {
+                        return (expanded, errors);
+                    }
+                },
+            }
+        }
+    }
+
+    (expanded, errors)
+}
@@ -0,0 +1,197 @@
+use std::path::Path;
+use assert_fs::assert::PathAssert;
+use assert_fs::fixture::PathChild;
+use assert_fs::fixture::FileWriteStr;
+use predicates::prelude::*;
+use shamv::{Algorithm, Clobber, Encoding, Error, ExtPolicy, Mode, Naming, Outcome, Plan, Shard, SuffixPolicy};
+
+#[test]
+fn hash_reader_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let mut alg = Algorithm::new("sha256")?;
+    let digest = shamv::hash_reader(&mut alg, "abc".as_bytes())?;
+
+    assert_eq!(hex::encode(digest), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+    Ok(())
+}
+
+#[test]
+fn hash_reader_with_progress_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let input = "a".repeat(1000000);
+    let mut chunks: Vec<usize> = Vec::new();
+
+    let mut alg = Algorithm::new("sha256")?;
+    let digest = shamv::hash_reader_with_progress(&mut alg, input.as_bytes(), |chunk| chunks.push(chunk.len()))?;
+
+    assert_eq!(hex::encode(digest), "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+    assert_eq!(chunks.iter().sum::<usize>(), input.len());
+    assert!(chunks.len() > 1);
+    assert!(chunks.iter().all(|&n| n <= shamv::CHUNK_SIZE));
+
+    Ok(())
+}
+
+#[test]
+fn hash_reader_of_git_blob() -> Result<(), Box<dyn std::error::Error>> {
+    let mut alg = Algorithm::new("git-sha256")?;
+    let digest = shamv::hash_reader(&mut alg, "abc".as_bytes())?;
+
+    assert_eq!(hex::encode(digest), "c1cf6e465077930e88dc5136641d402f72a229ddd996f627d60e9639eaba35a6");
+
+    Ok(())
+}
+
+#[test]
+fn destination_name_keeps_extension() {
+    let path = Path::new("dir/file_with_extension.txt");
+
+    assert_eq!(shamv::destination_name("abc", path, &Naming::default()), "abc.txt");
+    assert_eq!(shamv::destination_path("abc", path, &Naming::default()), Path::new("dir/abc.txt"));
+}
+
+#[test]
+fn destination_name_with_head_detects_extension() {
+    // The file does not exist, so its type can only be detected from the given bytes
+    let path = Path::new("dir/screenshot.jpg");
+    let naming = Naming { ext: ExtPolicy::Fix, ..Naming::default() };
+    let head = b"\x89PNG\r\n\x1a\n";
+
+    assert_eq!(shamv::destination_name_with_head("abc", path, head, &naming), "abc.png");
+    assert_eq!(shamv::destination_path_with_head("abc", path, head, &naming), Path::new("dir/abc.png"));
+    assert_eq!(shamv::destination_name("abc", path, &naming), "abc.jpg");
+}
+
+#[test]
+fn suffixes_with_policy() {
+    let suffixes = |name: &str, policy: &str| shamv::suffixes(Path::new(name), policy.parse().unwrap()).join(".");
+
+    assert_eq!(suffixes("backup.tar.gz", "all"), "tar.gz");
+    assert_eq!(suffixes("backup.tar.gz", "last"), "gz");
+    assert_eq!(suffixes("backup.tar.gz", "none"), "");
+    assert_eq!(suffixes("backup.tar.gz", "1"), "gz");
+    assert_eq!(suffixes("backup.tar.gz", "3"), "tar.gz");
+    assert_eq!(suffixes("photo.2023.tar.gz", "all"), "tar.gz");
+    assert_eq!(suffixes("my.backup.tar.gz", "all"), "backup.tar.gz");
+    assert_eq!(suffixes("my.backup.tar.gz", "known"), "tar.gz");
+    assert_eq!(suffixes("script.user.js", "known"), "user.js");
+    assert_eq!(suffixes("my.notes.txt", "known"), "txt");
+    assert_eq!(suffixes(".bashrc", "all"), "");
+    assert_eq!(suffixes(".config.json", "all"), "json");
+    assert_eq!(suffixes("v1.2 notes", "all"), "");
+    assert_eq!(suffixes("v1.2 notes.txt", "all"), "txt");
+    assert_eq!(suffixes("trailing.", "all"), "");
+    assert!("most".parse::<SuffixPolicy>().is_err());
+}
+
+#[test]
+fn unsupported_algorithm() {
+    assert!(matches!(Algorithm::new("md4"), Err(Error::UnsupportedAlg(_))));
+}
+
+#[test]
+fn plan_with_missing_file() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("file.txt");
+    file.write_str("abc")?;
+
+    let mut alg = Algorithm::default();
+    let result = Plan::new(&mut alg, &Naming::default(), [file.path(), Path::new("test/file/doesnt/exist")]);
+
+    assert!(matches!(result, Err(Error::FileNotFound(_))));
+    file.assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn plan_execute_renames_files() -> Result<(), Box<dyn std::error::Error>> {
+    let new_file_name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("file.txt");
+    file.write_str("abc")?;
+
+    let mut alg = Algorithm::default();
+    let plan = Plan::new(&mut alg, &Naming::default(), [file.path()])?;
+    assert_eq!(plan.entries()[0].destination, tmp_dir.child(new_file_name).path());
+
+    plan.execute(Mode::DryRun, Clobber::Verify)?;
+    file.assert(predicate::path::exists());
+
+    plan.execute(Mode::Rename, Clobber::Verify)?;
+    file.assert(predicate::path::missing());
+    tmp_dir.child(new_file_name).assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn apply_with_existing_destination() -> Result<(), Box<dyn std::error::Error>> {
+    let new_file_name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("file.txt");
+    file.write_str("abc")?;
+    let destination = tmp_dir.child(new_file_name);
+
+    let mut alg = Algorithm::default();
+    let plan = Plan::new(&mut alg, &Naming::default(), [file.path()])?;
+    let entry = &plan.entries()[0];
+
+    destination.write_str("abc")?;
+    assert_eq!(entry.apply(Mode::Rename, Clobber::Verify)?, Outcome::Identical);
+
+    destination.write_str("truncated")?;
+    assert!(matches!(entry.apply(Mode::Rename, Clobber::Verify), Err(Error::DestinationExists { .. })));
+    assert_eq!(entry.apply(Mode::Rename, Clobber::NoClobber)?, Outcome::Exists);
+    destination.assert("truncated");
+
+    assert_eq!(entry.apply(Mode::Rename, Clobber::Force)?, Outcome::Done);
+    destination.assert("abc");
+    file.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn encodings_round_trip() {
+    let digests: [&[u8]; 3] = [b"", b"\0\0abc", &[0xff; 64]];
+
+    for encoding in Encoding::ALL {
+        for digest in digests {
+            let encoded = encoding.encode(digest);
+            assert!(encoded.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+            assert_eq!(encoding.decode(&encoded).as_deref(), Some(digest), "{}", encoding);
+        }
+        assert_eq!(encoding.name().parse::<Encoding>(), Ok(*encoding));
+    }
+}
+
+#[test]
+fn shard_dirs() {
+    let shard = "2/2".parse::<Shard>().unwrap();
+    assert_eq!(shard.dirs("ab12cd"), Path::new("ab/12"));
+    assert_eq!(shard.dirs("ab1"), Path::new(""));
+    assert_eq!(shard.root(Path::new("store/ab/12"), "ab12cd"), Path::new("store"));
+    assert_eq!(shard.root(Path::new("store/ab"), "ab12cd"), Path::new("store/ab"));
+    assert_eq!(shard.path(Path::new("store"), "ab12cd.tar.gz"), Path::new("store/ab/12/ab12cd.tar.gz"));
+    assert_eq!(shard.to_string(), "2/2");
+    assert_eq!("0".parse::<Shard>(), Ok(Shard::default()));
+}
+
+#[test]
+fn detect_extension_from_magic_bytes() {
+    let heads: [(&[u8], Option<&str>); 7] = [
+        (b"\x89PNG\r\n\x1a\n", Some("png")),
+        (b"GIF89a", Some("gif")),
+        (b"RIFF\x24\0\0\0WEBPVP8 ", Some("webp")),
+        (b"%PDF-1.7", Some("pdf")),
+        (b"\x1f\x8b\x08\0", Some("gz")),
+        (b"\0\0\0\x20ftypisom\0\0\x02\0", Some("mp4")),
+        (b"plain text", None),
+    ];
+
+    for (head, extension) in heads {
+        assert_eq!(shamv::detect_extension(head), extension);
+    }
+}
@@ -4,6 +4,7 @@ use std::process::Command;
 use assert_fs::assert::PathAssert;
 use assert_fs::fixture::PathChild;
 use assert_fs::fixture::FileWriteStr;
+use assert_fs::fixture::FileWriteBin;
 
 #[test]
 fn file_does_not_exist() -> Result<(), Box<dyn std::error::Error>> {
@@ -17,6 +18,121 @@ fn file_does_not_exist() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn keep_going_with_missing_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "file_with_extension.txt";
+    let new_file_name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg(file.path()).arg("test/file/doesnt/exist");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("file not found"));
+
+    file.assert(predicate::path::exists());
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--keep-going").arg(file.path()).arg("test/file/doesnt/exist");
+    cmd.assert()
+        .code(shamv::Status::PartialFailure as i32)
+        .stderr(predicate::str::contains("file not found"));
+
+    file.assert(predicate::path::missing());
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn keep_going_with_existing_destination() -> Result<(), Box<dyn std::error::Error>> {
+    let destination_name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt";
+    let new_file_name = "3e23e8160039594a33894f6564e1b1348bbd7a0088d42c4acb73eeaed59c009d.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("a.txt");
+    file.write_str("abc")?;
+    let other_file = tmp_dir.child("b.txt");
+    other_file.write_str("b")?;
+    tmp_dir.child(destination_name).write_str("other content")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg(file.path()).arg(other_file.path());
+    cmd.assert()
+        .code(shamv::Status::DestinationExists as i32);
+
+    file.assert("abc");
+    other_file.assert("b");
+    tmp_dir.child(new_file_name).assert(predicate::path::missing());
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--keep-going").arg(file.path()).arg(other_file.path());
+    cmd.assert()
+        .code(shamv::Status::PartialFailure as i32);
+
+    file.assert("abc");
+    other_file.assert(predicate::path::missing());
+    tmp_dir.child(new_file_name).assert("b");
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn rename_failure() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("file_with_extension.txt");
+    file.write_str("abc")?;
+
+    // A dangling symbolic link at the destination path cannot be compared to the file
+    std::os::unix::fs::symlink("doesnt/exist",
+        tmp_dir.child("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt").path())?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg(file.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("error renaming file"));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_option() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algo").arg("sha256").arg("file");
+    cmd.assert()
+        .code(shamv::Status::UsageError as i32)
+        .stderr(predicate::str::starts_with("shamv: Unrecognized option: 'algo'"))
+        .stderr(predicate::str::contains("--help"))
+        .stderr(predicate::str::contains("panicked").not());
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--copy").arg("--link").arg("file");
+    cmd.assert()
+        .code(shamv::Status::UsageError as i32)
+        .stderr(predicate::str::contains("conflicting options --copy and --link"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--max-depth").arg("1").arg("file");
+    cmd.assert()
+        .code(shamv::Status::UsageError as i32)
+        .stderr(predicate::str::contains("option --max-depth requires --recursive"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--suffixes").arg("most").arg("file");
+    cmd.assert()
+        .code(shamv::Status::UsageError as i32)
+        .stderr(predicate::str::contains("invalid suffix policy most"));
+
+    Ok(())
+}
+
 #[test]
 fn arg_is_directory() -> Result<(), Box<dyn std::error::Error>> {
     let tmp_dir = assert_fs::TempDir::new()?;
@@ -30,6 +146,36 @@ fn arg_is_directory() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn recursive_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let new_file_name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt";
+    let new_empty_file_name = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("file_with_extension.txt");
+    file.write_str("abc")?;
+    let nested_file = tmp_dir.child("a/empty.txt");
+    nested_file.write_str("")?;
+    let deeply_nested_file = tmp_dir.child("a/b/empty.txt");
+    deeply_nested_file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--recursive").arg("--max-depth").arg("2").arg(tmp_dir.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+    tmp_dir
+        .child("a")
+        .child(new_empty_file_name)
+        .assert(predicate::path::exists());
+    deeply_nested_file.assert(predicate::path::exists());
+
+    Ok(())
+}
+
 #[test]
 fn default_algorithm() -> Result<(), Box<dyn std::error::Error>> {
     let old_file_name = "file_with_extension.txt";
@@ -111,6 +257,238 @@ fn file_without_extension() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn progress_of_large_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--progress").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name))
+        .stderr(predicate::str::contains("NIST.3.txt\": 100%"));
+
+    Ok(())
+}
+
+#[test]
+fn file_with_multiple_suffixes() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "backup.tar.gz";
+    let new_file_name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.tar.gz";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--suffixes").arg("last").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.gz")
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn existing_destination_with_different_content() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "file_with_extension.txt";
+    let new_file_name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+    let existing_file = tmp_dir.child(new_file_name);
+    existing_file.write_str("ab")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg(file.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("refusing to overwrite"));
+
+    file.assert(predicate::path::exists());
+    existing_file.assert("ab");
+
+    let mut cmd = assert_cmd::Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--interactive").arg(file.path()).write_stdin("n\n");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("overwrite"));
+
+    file.assert(predicate::path::exists());
+    existing_file.assert("ab");
+
+    let mut cmd = assert_cmd::Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--interactive").arg(file.path()).write_stdin("y\n");
+    cmd.assert()
+        .success();
+
+    file.assert(predicate::path::missing());
+    existing_file.assert("abc");
+
+    Ok(())
+}
+
+#[test]
+fn existing_destination_with_identical_content() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "file_with_extension.txt";
+    let new_file_name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+    let existing_file = tmp_dir.child(new_file_name);
+    existing_file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg(file.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("identical content"));
+
+    file.assert(predicate::path::exists());
+
+    let mut cmd = assert_cmd::Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--interactive").arg(file.path()).write_stdin("y\n");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("identical content"))
+        .stderr(predicate::str::contains("overwrite").not());
+
+    file.assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn copy_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "file_with_extension.txt";
+    let new_file_name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--copy").arg(file.path());
+    cmd.assert()
+        .success();
+
+    file.assert("abc");
+    tmp_dir
+        .child(new_file_name)
+        .assert("abc");
+    assert_eq!(std::fs::read_dir(tmp_dir.path())?.count(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn hard_link_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "file_with_extension.txt";
+    let new_file_name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--link").arg(file.path());
+    cmd.assert()
+        .success();
+
+    file.assert("abc");
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::is_file());
+    assert!(!std::fs::symlink_metadata(tmp_dir.child(new_file_name).path())?.is_symlink());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "file_with_extension.txt";
+    let new_file_name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--symlink").arg("--relative").arg(file.path());
+    cmd.assert()
+        .success();
+
+    assert_eq!(std::fs::read_link(tmp_dir.child(new_file_name).path())?, std::path::Path::new(old_file_name));
+    std::fs::remove_file(tmp_dir.child(new_file_name).path())?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--symlink").arg(file.path());
+    cmd.assert()
+        .success();
+
+    assert_eq!(std::fs::read_link(tmp_dir.child(new_file_name).path())?, std::fs::canonicalize(file.path())?);
+
+    Ok(())
+}
+
+#[test]
+fn check_file_names() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let sha224_file = tmp_dir.child("23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7.txt");
+    sha224_file.write_str("abc")?;
+    let sha512_file = tmp_dir.child("ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f");
+    sha512_file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg(sha224_file.path()).arg(sha512_file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("txt: OK (sha224)"))
+        .stdout(predicate::str::contains("a49f: OK (sha512)"));
+
+    let not_a_hash_file = tmp_dir.child("file_with_extension.txt");
+    not_a_hash_file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg(sha224_file.path()).arg(not_a_hash_file.path());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("file_with_extension.txt: NOT-A-HASH-NAME"));
+
+    sha224_file.write_str("abd")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg(sha224_file.path()).arg(sha512_file.path());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("txt: MISMATCH"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg("--algorithm").arg("sha256").arg(sha512_file.path());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("NOT-A-HASH-NAME"));
+
+    Ok(())
+}
+
 #[test]
 fn sha224_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
     let old_file_name = "empty.txt";
@@ -560,7 +938,7 @@ fn sha512_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
     cmd.assert().success();
 
     tmp_dir
-        .child(&new_file_name)
+        .child(new_file_name)
         .assert(predicate::path::exists());
 
     Ok(())
@@ -645,3 +1023,2036 @@ fn sha512_of_multiple_input_files() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn sha3_224_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "6b4e03423667dbb73b6e15454f0eb1abd4597f9a1b078e3f5b5a6bc7.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-224").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-224").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha3_224_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "e642824c3f8cf24ad09234ee7d3c766fc9a3a5168d0c94ad73b46fdf.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-224").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-224").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha3_224_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "d69335b93325192e516a912e6d19a15cb51c6ed5c15243e7a7fd653c.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-224").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-224").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha3_256_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha3_256_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha3_256_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "5c8875ae474a3634ba4fd55ec85bffd661f32aca75c6d699d0cdcb6c115891c1.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha3_384_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "0c63a75b845e4f7d01107d852e4c2485c51a50aaaa94fc61995e71bbee983a2ac3713831264adb47fb6bd1e058d5f004.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-384").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-384").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha3_384_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b298d88cea927ac7f539f1edf228376d25.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-384").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-384").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha3_384_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "eee9e24d78c1855337983451df97c8ad9eedf256c6334f8e948d252d5e0e76847aa0774ddb90a842190d2c558b4b8340.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-384").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-384").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha3_512_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a615b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-512").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-512").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha3_512_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-512").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-512").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha3_512_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "3c3a876da14034ab60627c077bb98f7e120a2a5370212dffb3385a18d4f38859ed311d0a9d5141ce9cc5c66ee689b266a8aa18ace8282a0e0db596c90b0a7b87.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha3-512").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha3-512").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn shake128_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("shake128").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("shake128").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn shake128_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("shake128").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("shake128").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn shake128_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "9d222c79c4ff9d092cf6ca86143aa411e369973808ef97093255826c5572ef58.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("shake128").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("shake128").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn shake256_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("shake256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("shake256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn shake256_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e4.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("shake256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("shake256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn shake256_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "3578a7a4ca9137569cdf76ed617d31bb994fca9c1bbf8b184013de8234dfd13a3fd124d4df76c0a539ee7dd2f6e1ec346124c815d9410e145eb561bcd97b18ab.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("shake256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("shake256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn shake128_160_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "5881092dd818bf5cf8a3ddb793fbcba74097d5c5.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("shake128-160").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("shake128-160").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn shake128_of_invalid_output_length() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("shake128-100").arg("file");
+    cmd.assert()
+        .code(shamv::Status::UnsupportedAlg as i32)
+        .stderr(predicate::str::contains("unsupported algorithm shake128-100"));
+
+    Ok(())
+}
+
+#[test]
+fn blake3_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake3").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake3").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake3_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake3").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake3").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake3_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "616f575a1b58d4c9797d4217b9730ae5e6eb319d76edef6549b46f4efe31ff8b.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake3").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake3").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake3_on_multiple_threads() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "large.bin";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_binary(&(0..40 * 1024 * 1024 + 17).map(|i| (i % 251) as u8).collect::<Vec<u8>>())?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake3").arg(file.path());
+    let single_threaded = cmd.output()?.stdout;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake3").arg("--threads").arg("4").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::eq(single_threaded));
+
+    Ok(())
+}
+
+#[test]
+fn blake2b_512_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake2b-512").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake2b-512").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake2b_512_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake2b-512").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake2b-512").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake2b_512_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "98fb3efb7206fd19ebf69b6f312cf7b64e3b94dbe1a17107913975a793f177e1d077609d7fba363cbba00d05f7aa4e4fa8715d6428104c0a75643b0ff3fd3eaf.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake2b-512").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake2b-512").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake2b_256_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake2b-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake2b-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake2b_256_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake2b-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake2b-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake2b_256_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "0741850f36cba4259628355d1073e24ddb9ca0e1bfac36fd39ae5dc2101e23a4.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake2b-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake2b-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake2s_256_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake2s-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake2s-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake2s_256_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake2s-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake2s-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake2s_256_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "bec0c0e6cde5b67acb73b81f79a67a4079ae1c60dac9d2661af18e9f8b50dfa5.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake2s-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake2s-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake2b_160_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "384264f676f39536840523f284921cdc68b6846b.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("blake2b-160").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake2b-160").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn blake2s_of_invalid_output_length() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("blake2s-512").arg("file");
+    cmd.assert()
+        .code(shamv::Status::UnsupportedAlg as i32)
+        .stderr(predicate::str::contains("unsupported algorithm blake2s-512"));
+
+    Ok(())
+}
+
+#[test]
+fn sha512_224_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "6ed0dd02806fa89e25de060c19d3ac86cabb87d6a0ddd05c333b84f4.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha512-224").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha512-224").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha512_224_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha512-224").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha512-224").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha512_224_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "37ab331d76f0d36de422bd0edeb22a28accd487b7a8453ae965dd287.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha512-224").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha512-224").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha512_256_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "empty.txt";
+    let new_file_name = "c672b8d1ef56ed28ab87c3622c5114069bdd3ad7b8f9737498d0c01ecef0967a.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha512-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha512-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha512_256_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha512-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha512-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn sha512_256_of_nist_3_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.3.txt";
+    let new_file_name = "9a59a052930187a97038cae692f30708aa6491923ef5194394dc68d56c74fb21.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str(&"a".repeat(1000000))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("sha512-256").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("sha512-256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn check_sha512_256_file_name() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let sha256_file = tmp_dir.child("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt");
+    sha256_file.write_str("abc")?;
+    let sha512_256_file = tmp_dir.child("53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23.txt");
+    sha512_256_file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg(sha256_file.path()).arg(sha512_256_file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("15ad.txt: OK (sha256)"))
+        .stdout(predicate::str::contains("af23.txt: OK (sha512-256)"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg("--algorithm").arg("sha256").arg(sha512_256_file.path());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("af23.txt: MISMATCH"));
+
+    Ok(())
+}
+
+#[test]
+fn encodings_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let encodings = [
+        ("hex", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt"),
+        ("base32", "xj4bnp4pahh6uqkbidpf3lrceoyagyndsylxvhfucd7wd4qacwwq.txt"),
+        ("base64url", "ungWv48Bz-pBQUDeXa4iI7ADYaOWF3qctBD_YfIAFa0.txt"),
+        ("base58", "DYu3G8aGTMBW1WrTw76zxQJQU4DHLw9MLyy7peG4LKkY.txt"),
+        ("nix32", "1b8m03r63zqhnjf7l5wnldhh7c134ap5vpj0850ymkq1iyzicy5s.txt"),
+    ];
+
+    for (encoding, new_file_name) in encodings {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let file = tmp_dir.child("NIST.1.txt");
+        file.write_str("abc")?;
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+        cmd.arg("--encoding").arg(encoding).arg(file.path());
+        cmd.assert()
+            .success();
+
+        file.assert(predicate::path::missing());
+        tmp_dir
+            .child(new_file_name)
+            .assert(predicate::path::exists());
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+        cmd.arg("--check").arg(tmp_dir.child(new_file_name).path());
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("txt: OK (sha256)"));
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+        cmd.arg("--check").arg("--encoding").arg(encoding).arg(tmp_dir.child(new_file_name).path());
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("txt: OK (sha256)"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn check_with_other_encoding() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("xj4bnp4pahh6uqkbidpf3lrceoyagyndsylxvhfucd7wd4qacwwq.txt");
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg("--encoding").arg("hex").arg(file.path());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("txt: NOT-A-HASH-NAME"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg("--encoding").arg("nix32").arg(file.path());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("txt: NOT-A-HASH-NAME"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg("--encoding").arg("base32").arg("--algorithm").arg("sha3-256").arg(file.path());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("txt: MISMATCH"));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_encoding() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--encoding").arg("base16").arg("file");
+    cmd.assert()
+        .code(shamv::Status::UsageError as i32)
+        .stderr(predicate::str::contains("invalid encoding base16"));
+
+    Ok(())
+}
+
+#[test]
+fn length_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("NIST.1.txt");
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--length").arg("8").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("ba7816bf.txt\" (length 8)"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--length").arg("8").arg(file.path());
+    cmd.assert()
+        .success();
+
+    file.assert(predicate::path::missing());
+    tmp_dir
+        .child("ba7816bf.txt")
+        .assert(predicate::path::exists());
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--length").arg("0").arg(file.path());
+    cmd.assert()
+        .code(shamv::Status::UsageError as i32)
+        .stderr(predicate::str::contains("invalid length 0"));
+
+    Ok(())
+}
+
+#[test]
+fn length_grows_with_existing_file_in_current_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("x.txt");
+    file.write_str("abc")?;
+    let existing_file = tmp_dir.child("b.txt");
+    existing_file.write_str("ab")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.current_dir(tmp_dir.path()).arg("--dry-run").arg("--length").arg("1").arg("x.txt");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"ba.txt\" (length 2)"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.current_dir(tmp_dir.path()).arg("--length").arg("1").arg("x.txt");
+    cmd.assert()
+        .success();
+
+    tmp_dir.child("ba.txt").assert("abc");
+    existing_file.assert("ab");
+
+    Ok(())
+}
+
+#[test]
+fn length_grows_with_prefix_shared_in_other_case() -> Result<(), Box<dyn std::error::Error>> {
+    // The base64url encoded SHA-256 hashes of "1" and "51" start with a4 and Ax
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("one.txt");
+    file.write_str("1")?;
+    let other_file = tmp_dir.child("other.txt");
+    other_file.write_str("51")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("-e").arg("base64url").arg("--length").arg("1").arg(file.path()).arg(other_file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("a4.txt\" (length 2)"))
+        .stdout(predicate::str::contains("Ax.txt\" (length 2)"));
+
+    Ok(())
+}
+
+#[test]
+fn length_grows_with_shared_prefix() -> Result<(), Box<dyn std::error::Error>> {
+    // The SHA-256 hashes of "abc" and "6924" share the prefix ba7
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let abc_file = tmp_dir.child("abc.txt");
+    abc_file.write_str("abc")?;
+    let copy_file = tmp_dir.child("copy.txt");
+    copy_file.write_str("abc")?;
+    let other_file = tmp_dir.child("other.txt");
+    other_file.write_str("6924")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--length").arg("2").arg(abc_file.path()).arg(copy_file.path()).arg(other_file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("ba78.txt\" (length 4)"))
+        .stdout(predicate::str::contains("ba74.txt\" (length 4)"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--length").arg("2").arg(abc_file.path()).arg(copy_file.path()).arg(other_file.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("identical content"));
+
+    tmp_dir.child("ba78.txt").assert("abc");
+    tmp_dir.child("ba74.txt").assert("6924");
+
+    Ok(())
+}
+
+#[test]
+fn length_grows_with_existing_file() -> Result<(), Box<dyn std::error::Error>> {
+    // The SHA-256 hashes of "abc" and "504" share the prefix ba
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let existing_file = tmp_dir.child("ba6.txt");
+    existing_file.write_str("504")?;
+    let file = tmp_dir.child("NIST.1.txt");
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--length").arg("2").arg(file.path());
+    cmd.assert()
+        .success();
+
+    existing_file.assert("504");
+    tmp_dir.child("ba7.txt").assert("abc");
+
+    // A file with the same content does not need a longer name
+    let file = tmp_dir.child("NIST.1.txt");
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--length").arg("3").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("ba7.txt\" (length 3)"));
+
+    Ok(())
+}
+
+#[test]
+fn multihash_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let multihashes = [
+        ("sha256", "hex", "f1220ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt"),
+        ("sha256", "base32", "bciqlu6awx6hqdt7kifaubxs5vyrchmadmgrzmf32ts2bb73b6iablli.txt"),
+        ("sha512-256", "hex", "f95202053048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23.txt"),
+        ("blake2b-160", "hex", "f94e40214384264f676f39536840523f284921cdc68b6846b.txt"),
+    ];
+
+    for (algorithm, encoding, new_file_name) in multihashes {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let file = tmp_dir.child("NIST.1.txt");
+        file.write_str("abc")?;
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+        cmd.arg("--multihash").arg("--algorithm").arg(algorithm).arg("--encoding").arg(encoding).arg(file.path());
+        cmd.assert()
+            .success();
+
+        file.assert(predicate::path::missing());
+        tmp_dir
+            .child(new_file_name)
+            .assert(predicate::path::exists());
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+        cmd.arg("--check").arg(tmp_dir.child(new_file_name).path());
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains(format!("txt: OK ({})", algorithm)));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn check_multihash_file_name() -> Result<(), Box<dyn std::error::Error>> {
+    // The multihash names SHA-256, so the file is not hashed with SHA-512/256
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("f122053048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23.txt");
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg(file.path());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("txt: MISMATCH"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--multihash").arg("--encoding").arg("nix32").arg(file.path());
+    cmd.assert()
+        .code(shamv::Status::UsageError as i32)
+        .stderr(predicate::str::contains("encoding nix32 has no multibase prefix"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--multihash").arg("--algorithm").arg("git-sha256").arg(file.path());
+    cmd.assert()
+        .code(shamv::Status::UsageError as i32)
+        .stderr(predicate::str::contains("algorithm git-sha256 has no multihash code"));
+
+    Ok(())
+}
+
+#[test]
+fn cid_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let new_file_name = "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("empty.txt");
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--cid").arg(file.path());
+    cmd.assert()
+        .success();
+
+    file.assert(predicate::path::missing());
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn cid_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let new_file_name = "bafkreif2pall7dybz7vecqka3zo24irdwabwdi4wc55jznaq75q7eaavvu.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("NIST.1.txt");
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--cid").arg(file.path());
+    cmd.assert()
+        .success();
+
+    file.assert(predicate::path::missing());
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg(tmp_dir.child(new_file_name).path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("txt: OK (cid)"));
+
+    Ok(())
+}
+
+/// Returns the CID that `ipfs add --cid-version=1 --raw-leaves` gives the file at `path`, or
+/// `None` if the `ipfs` command is not available.
+fn ipfs_add(path: &std::path::Path) -> Option<String> {
+    let output = Command::new("ipfs")
+        .args(["add", "--only-hash", "--quiet", "--cid-version=1", "--raw-leaves"])
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// The balanced layout that the names of chunked files below are calculated with reproduces the
+// root CID of a DAG exported from kubo, the config.toml.size-1 fixture of the rs-car crate: 400
+// leaves of one byte in nodes of 174, 174 and 52 links. That fixture has CIDv0 and dag-pb leaves,
+// so the names have not been produced by `ipfs add` itself. Where the `ipfs` command is
+// available, they are also compared to the CIDs that `ipfs add` gives the files.
+
+#[test]
+fn cid_of_chunked_file() -> Result<(), Box<dyn std::error::Error>> {
+    // A file of one 256 KiB chunk is a single raw block, one more byte makes it a UnixFS DAG
+    let one_chunk_file_name = "bafkreiekhhjkxu4ztk3tyng3er3ijhg56mb44oe3gwbgquhzu4afrg2ksa";
+    let two_chunk_file_name = "bafybeigllfqgfpqydppr6cmv56g7ax4wyhruzswvcefv6j5kj77nzttfki";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let one_chunk_file = tmp_dir.child("one_chunk");
+    one_chunk_file.write_binary(&[0; 256 * 1024])?;
+    let two_chunk_file = tmp_dir.child("two_chunk");
+    two_chunk_file.write_binary(&[0; 256 * 1024 + 1])?;
+
+    if let Some(cid) = ipfs_add(one_chunk_file.path()) {
+        assert_eq!(cid, one_chunk_file_name);
+    }
+    if let Some(cid) = ipfs_add(two_chunk_file.path()) {
+        assert_eq!(cid, two_chunk_file_name);
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--cid").arg(one_chunk_file.path()).arg(two_chunk_file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(one_chunk_file_name)
+        .assert(predicate::path::exists());
+    tmp_dir
+        .child(two_chunk_file_name)
+        .assert(predicate::path::exists());
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg(tmp_dir.child(two_chunk_file_name).path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(format!("{}: OK (cid)", two_chunk_file_name)));
+
+    Ok(())
+}
+
+#[test]
+fn cid_of_file_with_more_chunks_than_links() -> Result<(), Box<dyn std::error::Error>> {
+    // 176 chunks do not fit in one node of at most 174 links, so the root links to a full node of
+    // 174 leaves and a node of the last 2, as with the balanced layout of `ipfs add`
+    let new_file_name = "bafybeihzhgkxa5ea4r5cr73r73tsf3ppwpstcxnsuyqxio3vy7xpikkhmi";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("many_chunks");
+    let content: Vec<u8> = (0..175 * 256 * 1024 + 1).map(|i| (i % 251) as u8).collect();
+    file.write_binary(&content)?;
+
+    if let Some(cid) = ipfs_add(file.path()) {
+        assert_eq!(cid, new_file_name);
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--cid").arg(file.path());
+    cmd.assert()
+        .success();
+
+    file.assert(predicate::path::missing());
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn git_sha1_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let new_file_name = "f2ba8f84ab5c1bce84a7b441cb1959cfc7093b7f.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("NIST.1.txt");
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("git-sha1").arg(file.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("warning: git-sha1 is vulnerable to collisions"));
+
+    file.assert(predicate::path::missing());
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg(tmp_dir.child(new_file_name).path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("txt: OK (git-sha1)"));
+
+    Ok(())
+}
+
+#[test]
+fn git_sha256_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let new_file_name = "c1cf6e465077930e88dc5136641d402f72a229ddd996f627d60e9639eaba35a6.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("NIST.1.txt");
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("git-sha256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    file.assert(predicate::path::missing());
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn git_sha256_of_empty_file() -> Result<(), Box<dyn std::error::Error>> {
+    let new_file_name = "473a0f4c3be8a93681a267e3b1e9a7dcda1185436fe141f7749120a303721813.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("empty.txt");
+    file.write_str("")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("git-sha256").arg(file.path());
+    cmd.assert()
+        .success();
+
+    file.assert(predicate::path::missing());
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn format_with_placeholders() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("notes.tar.gz");
+    file.write_str("abc")?;
+    let other_file = tmp_dir.child("README");
+    other_file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--copy").arg("--format").arg("{algo}/{hash:2}/{stem}-{size}-{hash}.{ext}").arg(file.path()).arg(other_file.path());
+    cmd.assert()
+        .success();
+
+    file.assert(predicate::path::exists());
+    tmp_dir.child("sha256/ba/notes-3-ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.gz").assert("abc");
+    tmp_dir.child("sha256/ba/README-3-ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad").assert("abc");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--format").arg("{{{hash}}}.{exts}").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("{ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad}.tar.gz"));
+
+    Ok(())
+}
+
+#[test]
+fn format_with_mtime() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("NIST.1.txt");
+    file.write_str("abc")?;
+    // Noon on 2023-06-15 UTC, which is the same date in most timezones
+    std::fs::File::options().write(true).open(file.path())?
+        .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1686830400))?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--format").arg("{mtime:%Y}/{mtime}-{hash}.{ext}").arg(file.path());
+    cmd.assert()
+        .success();
+
+    file.assert(predicate::path::missing());
+    tmp_dir
+        .child("2023/2023-06-15-ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt")
+        .assert("abc");
+
+    Ok(())
+}
+
+#[test]
+fn invalid_format() -> Result<(), Box<dyn std::error::Error>> {
+    let formats = [
+        ("{stem}.{ext}", "must include the full {hash}"),
+        ("{hash:2}", "must include the full {hash}"),
+        ("{hash}.{extension}", "unknown placeholder {extension}"),
+        ("{hash:0}", "invalid hash length 0"),
+        ("{hash", "unmatched {"),
+        ("../{hash}", "must be a relative path"),
+        ("/{hash}", "must be a relative path"),
+    ];
+
+    for (format, message) in formats {
+        let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+        cmd.arg("--format").arg(format).arg("file");
+        cmd.assert()
+            .code(shamv::Status::UsageError as i32)
+            .stderr(predicate::str::contains(message));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn shard() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("file.txt");
+    file.write_str("abc")?;
+    let other_file = tmp_dir.child("other.txt");
+    other_file.write_str("abc\n")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--shard").arg("2/2").arg(file.path());
+    cmd.assert()
+        .success();
+
+    let destination = tmp_dir.child("ba/78/ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt");
+    file.assert(predicate::path::missing());
+    destination.assert("abc");
+
+    // Files that already are in place are not sharded again
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--shard").arg("2/2").arg("--recursive").arg(tmp_dir.path());
+    cmd.assert()
+        .success();
+
+    destination.assert("abc");
+    other_file.assert(predicate::path::missing());
+    tmp_dir.child("ed/ea/edeaaff3f1774ad2888673770c6d64097e391bc362d7d6fb34982ddf0efd18cb.txt")
+        .assert("abc\n");
+
+    Ok(())
+}
+
+#[test]
+fn shard_with_length() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("file.txt");
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--shard").arg("2/2").arg("--length").arg("2").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir.child("ba/78/ba78.txt").assert("abc");
+
+    Ok(())
+}
+
+#[test]
+fn check_shard() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("ba/ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt");
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg("--shard").arg("2").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("OK (sha256)"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg("--shard").arg("2/2").arg(file.path());
+    cmd.assert()
+        .code(shamv::Status::Mismatch as i32)
+        .stdout(predicate::str::contains("MISPLACED (sha256)"));
+
+    Ok(())
+}
+
+#[test]
+fn reshard() -> Result<(), Box<dyn std::error::Error>> {
+    let name = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(format!("ba/{}", name));
+    file.write_str("abc")?;
+    let other_file = tmp_dir.child("ba/notes.txt");
+    other_file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--reshard").arg("--shard").arg("2/2").arg(tmp_dir.path());
+    cmd.assert()
+        .success();
+
+    file.assert(predicate::path::missing());
+    tmp_dir.child(format!("ba/78/{}", name)).assert("abc");
+    other_file.assert("abc");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--reshard").arg(tmp_dir.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir.child(name).assert("abc");
+    tmp_dir.child("ba/78").assert(predicate::path::missing());
+    other_file.assert("abc");
+
+    Ok(())
+}
+
+#[test]
+fn reshard_shortened_names() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("x.txt");
+    file.write_str("abc")?;
+    let readme = tmp_dir.child("README.md");
+    readme.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--length").arg("10").arg("--shard").arg("2").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir.child("ba/ba7816bf8f.txt").assert("abc");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--reshard").arg("--shard").arg("2/2").arg(tmp_dir.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir.child("ba/78/ba7816bf8f.txt").assert("abc");
+    tmp_dir.child("ba/ba7816bf8f.txt").assert(predicate::path::missing());
+    readme.assert("abc");
+
+    Ok(())
+}
+
+#[test]
+fn invalid_shard() -> Result<(), Box<dyn std::error::Error>> {
+    for shard in ["", "x", "2/0", "2//2"] {
+        let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+        cmd.arg("--shard").arg(shard).arg("file");
+        cmd.assert()
+            .code(shamv::Status::UsageError as i32)
+            .stderr(predicate::str::contains("invalid shard layout"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn target_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("notes/file.txt");
+    file.write_str("abc")?;
+    let other_file = tmp_dir.child("downloads/copy.txt");
+    other_file.write_str("abc")?;
+    let store = tmp_dir.child("store");
+    std::fs::create_dir(store.path())?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--target-directory").arg(store.path()).arg(file.path()).arg(other_file.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("has identical content"));
+
+    file.assert(predicate::path::missing());
+    other_file.assert("abc");
+    store.child("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt").assert("abc");
+
+    Ok(())
+}
+
+#[test]
+fn target_directory_with_different_content() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("notes/file.txt");
+    file.write_str("abc")?;
+    let store = tmp_dir.child("store");
+    let destination = store.child("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt");
+    destination.write_str("truncated")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("-t").arg(store.path()).arg(file.path());
+    cmd.assert()
+        .code(shamv::Status::DestinationExists as i32);
+
+    file.assert("abc");
+    destination.assert("truncated");
+
+    Ok(())
+}
+
+#[test]
+fn target_directory_with_parents() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("file.txt");
+    file.write_str("abc")?;
+    let store = tmp_dir.child("archive/store");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("-t").arg(store.path()).arg(file.path());
+    cmd.assert()
+        .code(shamv::Status::FileNotFound as i32)
+        .stderr(predicate::str::contains("target directory"));
+
+    file.assert("abc");
+    store.assert(predicate::path::missing());
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("-t").arg(store.path()).arg("--parents").arg(file.path());
+    cmd.assert()
+        .success();
+
+    file.assert(predicate::path::missing());
+    store.child("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt").assert("abc");
+
+    Ok(())
+}
+
+#[test]
+fn normalize_ext() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let files = ["IMG.JPG", "img.jpeg", "img.jpg"].map(|name| tmp_dir.child(name));
+    for file in &files {
+        file.write_str("abc")?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.env("XDG_CONFIG_HOME", tmp_dir.path()).arg("--normalize-ext").args(files.iter().map(|file| file.path()));
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("has identical content").count(2));
+
+    tmp_dir.child("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.jpg").assert("abc");
+    tmp_dir.child("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.jpeg")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn normalize_ext_with_config() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("config.YML");
+    file.write_str("abc")?;
+    let config = tmp_dir.child("shamv/extensions");
+    config.write_str("# Extensions of YAML files\nyaml yml\n")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.env("XDG_CONFIG_HOME", tmp_dir.path()).arg("-n").arg("--normalize-ext").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.yaml"));
+
+    config.write_str("yaml\n")?;
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.env("XDG_CONFIG_HOME", tmp_dir.path()).arg("-n").arg("--normalize-ext").arg(file.path());
+    cmd.assert()
+        .code(shamv::Status::UsageError as i32)
+        .stderr(predicate::str::contains("invalid extension aliases on line 1: yaml"));
+
+    Ok(())
+}
+
+#[test]
+fn ext_detect() -> Result<(), Box<dyn std::error::Error>> {
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    let jpeg = b"\xff\xd8\xff\xe0\0\x10JFIF\0";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("screenshot");
+    file.write_binary(png)?;
+    let other_file = tmp_dir.child("photo.png");
+    other_file.write_binary(jpeg)?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--ext=detect").arg(file.path()).arg(other_file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"screenshot\x22 → \x22.*/[0-9a-f]{64}\.png\x22")?)
+        .stdout(predicate::str::is_match(r"photo\.png\x22 → \x22.*/[0-9a-f]{64}\.png\x22")?);
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--ext=fix").arg(other_file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"[0-9a-f]{64}\.jpg\x22")?);
+
+    Ok(())
+}
+
+#[test]
+fn ext_fix_keeps_extensions_of_the_type() -> Result<(), Box<dyn std::error::Error>> {
+    let zip = b"PK\x03\x04\x14\0\0\0\x08\0";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child("report.docx");
+    file.write_binary(zip)?;
+    let other_file = tmp_dir.child("archive.txt");
+    other_file.write_binary(zip)?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--copy").arg("--ext=fix").arg(file.path()).arg(other_file.path());
+    cmd.assert()
+        .success();
+
+    let digest = "3d2cf2492a6475c87dbec5dd2ba8bdb2670350e80a5f7047d9e32007691079aa";
+    tmp_dir.child(format!("{}.docx", digest)).assert(predicate::path::exists());
+    tmp_dir.child(format!("{}.zip", digest)).assert(predicate::path::exists());
+    tmp_dir.child(format!("{}.txt", digest)).assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn invalid_ext() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--ext=guess").arg("file");
+    cmd.assert()
+        .code(shamv::Status::UsageError as i32)
+        .stderr(predicate::str::contains("invalid extension policy guess"));
+
+    Ok(())
+}
+
+#[cfg(not(feature = "legacy-hashes"))]
+#[test]
+fn md5_without_legacy_hashes() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("md5").arg("file");
+    cmd.assert()
+        .code(shamv::Status::UnsupportedAlg as i32)
+        .stderr(predicate::str::contains("unsupported algorithm md5"));
+
+    Ok(())
+}
+
+#[cfg(feature = "legacy-hashes")]
+#[test]
+fn md5_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let old_file_name = "NIST.1.txt";
+    let new_file_name = "900150983cd24fb0d6963f7d28e17f72.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(old_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--dry-run").arg("--algorithm").arg("md5").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(new_file_name))
+        .stderr(predicate::str::contains("vulnerable to collisions"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("md5").arg(file.path());
+    cmd.assert()
+        .code(shamv::Status::UsageError as i32)
+        .stderr(predicate::str::contains("without --allow-weak"));
+
+    file.assert(predicate::path::exists());
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--algorithm").arg("md5").arg("--allow-weak").arg(file.path());
+    cmd.assert()
+        .success();
+
+    tmp_dir
+        .child(new_file_name)
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[cfg(feature = "legacy-hashes")]
+#[test]
+fn sha1_of_nist_1_test_vector() -> Result<(), Box<dyn std::error::Error>> {
+    let new_file_name = "a9993e364706816aba3e25717850c26c9cd0d89d.txt";
+
+    let tmp_dir = assert_fs::TempDir::new()?;
+    let file = tmp_dir.child(new_file_name);
+    file.write_str("abc")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("OK (sha1)"));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_CRATE_NAME"))?;
+    cmd.arg("--check").arg("--algorithm").arg("sha1").arg(file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("OK (sha1)"))
+        .stderr(predicate::str::contains("vulnerable to collisions"));
+
+    Ok(())
+}